@@ -12,17 +12,20 @@ mod trie;
 
 use dfa::minimizer::Minimizer;
 use dfa::prefix_searcher::PrefixSearcher;
-use graph::Graph;
+use graph::{dot_escape, range_label, write_to_string, Graph};
 use itertools::Itertools;
 use look::Look;
 use nfa::{Accept, StateIdx};
-use range_map::{RangeMap, RangeMultiMap};
+use range_map::{Range, RangeMap, RangeMultiMap};
 use refinery::Partition;
 use runner::program::TableInsts;
 use std;
-use std::fmt::{Debug, Formatter};
+use std::cmp::max;
+use std::collections::HashSet;
+use std::fmt::{Debug, Formatter, Write};
 use std::hash::Hash;
 use std::mem;
+use std::mem::size_of;
 use std::u32;
 
 pub use dfa::prefix_searcher::PrefixPart;
@@ -44,6 +47,20 @@ impl<Ret> State<Ret> {
     }
 }
 
+/// Some statistics about the size of a `Dfa`, as returned by `Dfa::stats`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DfaStats {
+    /// The number of states.
+    pub num_states: usize,
+    /// The number of transition ranges, summed over all states.
+    pub num_ranges: usize,
+    /// The number of distinct states that are the target of some transition.
+    pub num_targets: usize,
+    /// The number of classes of bytes that the `Dfa` can't tell apart (i.e., bytes in the same
+    /// class have the same transitions in every state).
+    pub num_byte_classes: usize,
+}
+
 pub trait RetTrait: Clone + Copy + Debug + Eq + Hash {}
 impl<T: Clone + Copy + Debug + Eq + Hash> RetTrait for T {}
 
@@ -113,6 +130,37 @@ impl<Ret: RetTrait> Dfa<Ret> {
         &self.states[state].accept
     }
 
+    /// Returns true if the given state accepts, at least at the end of the input.
+    pub fn is_accepting(&self, state: StateIdx) -> bool {
+        self.states[state].accept != Accept::Never
+    }
+
+    /// Returns a rough estimate of the number of bytes of memory (stack and heap) used by me.
+    pub fn approx_memory_usage(&self) -> usize {
+        let trans_size = size_of::<(Range<u8>, StateIdx)>();
+        let trans: usize = self.states
+            .iter()
+            .map(|st| st.transitions.num_ranges() * trans_size)
+            .sum();
+
+        size_of::<Dfa<Ret>>()
+            + self.states.capacity() * size_of::<State<Ret>>()
+            + self.init.capacity() * size_of::<Option<StateIdx>>()
+            + trans
+    }
+
+    /// Returns true if this `Dfa` matches the empty string.
+    ///
+    /// That is, returns true if one of the initial states that may be used at the beginning of
+    /// the input also accepts at the end of the input.
+    pub fn accepts_empty(&self) -> bool {
+        Look::all()
+            .iter()
+            .filter(|look| look.allows_eoi())
+            .filter_map(|&look| self.init_state(look))
+            .any(|s| self.is_accepting(s))
+    }
+
     /// The value that will be returned if we accept in state `state`.
     pub fn ret(&self, state: StateIdx) -> Option<&Ret> {
         self.states[state].ret.as_ref()
@@ -205,6 +253,22 @@ impl<Ret: RetTrait> Dfa<Ret> {
         (ret, 32 - size.leading_zeros())
     }
 
+    /// Returns some statistics about the size of this `Dfa`.
+    pub fn stats(&self) -> DfaStats {
+        let targets: HashSet<StateIdx> = self.states
+            .iter()
+            .flat_map(|st| st.transitions.ranges_values().map(|&(_, target)| target))
+            .collect();
+        let (byte_class, _) = self.byte_equivalence_classes();
+
+        DfaStats {
+            num_states: self.num_states(),
+            num_ranges: self.states.iter().map(|st| st.transitions.num_ranges()).sum(),
+            num_targets: targets.len(),
+            num_byte_classes: byte_class.iter().cloned().max().unwrap_or(0) as usize + 1,
+        }
+    }
+
     /// Compiles this `Dfa` into instructions for execution.
     pub fn compile(&self) -> TableInsts<Ret> {
         let (byte_class, log_num_classes) = self.byte_equivalence_classes();
@@ -249,6 +313,140 @@ impl<Ret: RetTrait> Dfa<Ret> {
         }
     }
 
+    /// Returns the source code of a standalone Rust function that runs this `Dfa`.
+    ///
+    /// The generated function has the signature `pub fn <fn_name>(input: &str) -> bool`, and it
+    /// returns true if this `Dfa`, started from `init_at_start()`, accepts some prefix of
+    /// `input`. To search for matches anywhere in the input, build the `Dfa` from an anchored
+    /// `Nfa` (see `Nfa::anchor`). Return values are ignored, and the generated code doesn't
+    /// depend on this crate. It uses `..=` patterns, so it needs Rust 1.26 or later.
+    ///
+    /// # Panics
+    /// - if `fn_name` isn't a valid Rust identifier.
+    pub fn to_rust_fn(&self, fn_name: &str) -> String {
+        // Returns a pattern matching all the states whose acceptance satisfies `pred`.
+        fn state_pattern<R, F: Fn(Accept) -> bool>(states: &[State<R>], pred: F) -> String {
+            let pats: Vec<String> = states
+                .iter()
+                .enumerate()
+                .filter(|&(_, st)| pred(st.accept))
+                .map(|(idx, _)| idx.to_string())
+                .collect();
+            pats.join(" | ")
+        }
+
+        if !is_identifier(fn_name) {
+            panic!("{:?} is not a valid function name", fn_name);
+        }
+
+        let init = match self.init_at_start() {
+            Some(s) => s,
+            None => return format!("pub fn {}(_input: &str) -> bool {{\n    false\n}}\n", fn_name),
+        };
+        let always = state_pattern(&self.states, |a| a == Accept::Always);
+        let accepting = state_pattern(&self.states, |a| a != Accept::Never);
+
+        write_to_string(|ret| {
+            try!(writeln!(ret, "pub fn {}(input: &str) -> bool {{", fn_name));
+            try!(writeln!(ret, "    let mut state: usize = {};", init));
+            try!(writeln!(ret, "    for &b in input.as_bytes() {{"));
+            if !always.is_empty() {
+                try!(writeln!(ret, "        match state {{"));
+                try!(writeln!(ret, "            {} => return true,", always));
+                try!(writeln!(ret, "            _ => {{}},"));
+                try!(writeln!(ret, "        }}"));
+            }
+            try!(writeln!(ret, "        state = match (state, b) {{"));
+            for (idx, st) in self.states.iter().enumerate() {
+                for &(range, target) in st.transitions.ranges_values() {
+                    if range.start == range.end {
+                        try!(writeln!(
+                            ret,
+                            "            ({}, {}) => {},",
+                            idx, range.start, target
+                        ));
+                    } else {
+                        try!(writeln!(
+                            ret,
+                            "            ({}, {}..={}) => {},",
+                            idx, range.start, range.end, target
+                        ));
+                    }
+                }
+            }
+            try!(writeln!(ret, "            _ => return false,"));
+            try!(writeln!(ret, "        }};"));
+            try!(writeln!(ret, "    }}"));
+            try!(writeln!(ret, "    match state {{"));
+            if !accepting.is_empty() {
+                try!(writeln!(ret, "        {} => true,", accepting));
+            }
+            try!(writeln!(ret, "        _ => false,"));
+            try!(writeln!(ret, "    }}"));
+            try!(writeln!(ret, "}}"));
+            Ok(())
+        })
+    }
+
+    /// Returns a description of this `Dfa` in Graphviz's DOT format.
+    ///
+    /// Accepting states are drawn with a double circle, and the initial states are pointed to by
+    /// bold edges from an extra point-shaped node (labelled by the look-behind they require).
+    /// There is one edge for each pair of states with a transition between them, labelled by a
+    /// comma-separated list of byte ranges. Printable ASCII bytes are shown as characters, and
+    /// other bytes in hex.
+    pub fn to_dot(&self) -> String {
+        write_to_string(|ret| {
+            ret.push_str("digraph dfa {\n\trankdir=LR;\n");
+            if self.init.iter().any(|x| x.is_some()) {
+                ret.push_str("\tinit [shape=point];\n");
+            }
+            for (idx, st) in self.states.iter().enumerate() {
+                let shape = if st.accept == Accept::Never {
+                    "circle"
+                } else {
+                    "doublecircle"
+                };
+                try!(writeln!(ret, "\t{} [shape={}];", idx, shape));
+            }
+            for &look in Look::all() {
+                if let Some(idx) = self.init_state(look) {
+                    let label = format!("{:?}", look);
+                    try!(writeln!(
+                        ret,
+                        "\tinit -> {} [label=\"{}\", style=bold];",
+                        idx,
+                        dot_escape(&label)
+                    ));
+                }
+            }
+            for (idx, st) in self.states.iter().enumerate() {
+                // Collect the ranges going to each target, in order of first appearance.
+                let mut edges: Vec<(StateIdx, Vec<String>)> = Vec::new();
+                for &(range, target) in st.transitions.ranges_values() {
+                    let label = range_label(range);
+                    if let Some(pos) = edges.iter().position(|e| e.0 == target) {
+                        edges[pos].1.push(label);
+                    } else {
+                        edges.push((target, vec![label]));
+                    }
+                }
+                for (target, labels) in edges {
+                    let label = labels.join(", ");
+                    try!(writeln!(
+                        ret,
+                        "\t{} -> {} [label=\"{}\"];",
+                        idx,
+                        target,
+                        dot_escape(&label)
+                    ));
+                }
+            }
+            ret.push_str("}\n");
+            Ok(())
+        })
+    }
+
     /// Finds an equivalent DFA with the minimal number of states.
     pub fn optimize(self) -> Dfa<Ret> {
         let mut ret = self.minimize();
@@ -256,6 +454,183 @@ impl<Ret: RetTrait> Dfa<Ret> {
         ret
     }
 
+    /// Returns true if `self` and `other` behave the same way on every input.
+    ///
+    /// That is, they accept the same strings, under the same conditions, and with the same return
+    /// values, starting from every possible initial state. The two `Dfa`s may have different
+    /// numbers of states; states that can never lead to an accepting state are treated the same
+    /// as a missing transition. This uses the union-find algorithm of Hopcroft and Karp.
+    pub fn equivalent(&self, other: &Dfa<Ret>) -> bool {
+        fn find(parent: &mut [usize], mut x: usize) -> usize {
+            while parent[x] != x {
+                parent[x] = parent[parent[x]];
+                x = parent[x];
+            }
+            x
+        }
+
+        let n = self.num_states();
+        let dead = n + other.num_states();
+        let self_live = self.live_states();
+        let other_live = other.live_states();
+
+        // States of `self` are numbered 0..n, states of `other` come next, and then there is a
+        // single dead state shared between them.
+        let node = |x: Option<StateIdx>, offset: usize, live: &[bool]| match x {
+            Some(s) if live[s] => s + offset,
+            _ => dead,
+        };
+        let label = |x: usize| {
+            if x == dead {
+                (Accept::Never, None)
+            } else if x < n {
+                (self.states[x].accept, self.states[x].ret)
+            } else {
+                (other.states[x - n].accept, other.states[x - n].ret)
+            }
+        };
+        let next = |x: usize, b: u8| {
+            if x == dead {
+                dead
+            } else if x < n {
+                node(self.states[x].transitions.get(b).cloned(), 0, &self_live[..])
+            } else {
+                node(other.states[x - n].transitions.get(b).cloned(), n, &other_live[..])
+            }
+        };
+
+        let mut parent: Vec<usize> = (0..(dead + 1)).collect();
+        let mut stack = Vec::new();
+        for &look in Look::all() {
+            let a = node(self.init_state(look), 0, &self_live[..]);
+            let b = node(other.init_state(look), n, &other_live[..]);
+            let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+            if ra != rb {
+                parent[ra] = rb;
+                stack.push((a, b));
+            }
+        }
+
+        while let Some((a, b)) = stack.pop() {
+            if label(a) != label(b) {
+                return false;
+            }
+            for byte in 0..256 {
+                let (a_next, b_next) = (next(a, byte as u8), next(b, byte as u8));
+                let (ra, rb) = (find(&mut parent, a_next), find(&mut parent, b_next));
+                if ra != rb {
+                    parent[ra] = rb;
+                    stack.push((a_next, b_next));
+                }
+            }
+        }
+        true
+    }
+
+    /// Returns the states that can never lead to an accepting state.
+    pub fn dead_states(&self) -> Vec<StateIdx> {
+        self.live_states()
+            .into_iter()
+            .enumerate()
+            .filter(|&(_, live)| !live)
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// Removes the states that can never lead to an accepting state, along with all transitions
+    /// leading into them.
+    ///
+    /// Initial states are never removed, even if they are dead (although their transitions into
+    /// dead states are). The remaining states keep their relative order.
+    pub fn remove_dead_states(&mut self) {
+        let live = self.live_states();
+        let mut keep = live.clone();
+        for &s in self.init.iter().filter_map(|x| x.as_ref()) {
+            keep[s] = true;
+        }
+
+        let mut old_to_new = vec![None; self.num_states()];
+        let mut next = 0;
+        for (old, new) in old_to_new.iter_mut().enumerate() {
+            if keep[old] {
+                *new = Some(next);
+                next += 1;
+            }
+        }
+
+        let old_states = mem::replace(&mut self.states, Vec::new());
+        self.states = old_states
+            .into_iter()
+            .enumerate()
+            .filter(|&(idx, _)| keep[idx])
+            .map(|(_, mut st)| {
+                st.transitions.retain_values(|x| live[*x]);
+                st
+            })
+            .collect();
+        self.map_states(|s| old_to_new[s].unwrap());
+    }
+
+    /// Makes the transition function total, by sending every missing transition to a new dead
+    /// state.
+    ///
+    /// Returns the index of the new dead state, or `None` if every state already had a transition
+    /// for every byte (in which case nothing is changed). This doesn't change what the `Dfa`
+    /// matches, but a compiled complete `Dfa` will keep running to the end of the input instead of
+    /// stopping when the match fails. Call `remove_dead_states` to undo this.
+    pub fn complete(&mut self) -> Option<StateIdx> {
+        let is_total = |st: &State<Ret>| {
+            st.transitions
+                .ranges_values()
+                .map(|&(r, _)| r.end as usize - r.start as usize + 1)
+                .sum::<usize>() == 256
+        };
+        if self.states.iter().all(is_total) {
+            return None;
+        }
+
+        let dead = self.add_state(Accept::Never, None);
+        for st in &mut self.states {
+            let mut trans = Vec::new();
+            // The first byte that we haven't seen a transition for yet.
+            let mut next = 0usize;
+            for &(range, target) in st.transitions.ranges_values() {
+                if range.start as usize > next {
+                    trans.push((Range::new(next as u8, range.start - 1), dead));
+                }
+                trans.push((range, target));
+                next = range.end as usize + 1;
+            }
+            if next < 256 {
+                trans.push((Range::new(next as u8, 255), dead));
+            }
+            st.transitions = trans.into_iter().collect();
+        }
+        Some(dead)
+    }
+
+    // Returns a vector saying, for each state, whether it can reach an accepting state.
+    fn live_states(&self) -> Vec<bool> {
+        let rev = self.reversed_transitions();
+        let mut live = vec![false; self.num_states()];
+        let mut stack: Vec<StateIdx> = (0..self.num_states())
+            .filter(|&s| self.states[s].accept != Accept::Never)
+            .collect();
+        for &s in &stack {
+            live[s] = true;
+        }
+
+        while let Some(s) = stack.pop() {
+            for &(_, source) in rev[s].ranges_values() {
+                if !live[source] {
+                    live[source] = true;
+                    stack.push(source);
+                }
+            }
+        }
+        live
+    }
+
     /// Deletes any transitions that return to the initial state.
     ///
     /// This results in a new Dfa with the following properties:
@@ -403,6 +778,80 @@ impl<Ret: RetTrait> Dfa<Ret> {
     */
 }
 
+// Is `s` something that we can use as the name of a Rust function?
+fn is_identifier(s: &str) -> bool {
+    const KEYWORDS: &'static [&'static str] = &[
+        "_", "abstract", "as", "async", "await", "become", "box", "break", "const", "continue",
+        "crate", "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if",
+        "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv",
+        "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true",
+        "try", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+    ];
+
+    let mut chars = s.chars();
+    let first_ok = match chars.next() {
+        Some('a'...'z') | Some('A'...'Z') | Some('_') => true,
+        _ => false,
+    };
+    let rest_ok = chars.all(|c| match c {
+        'a'...'z' | 'A'...'Z' | '0'...'9' | '_' => true,
+        _ => false,
+    });
+    first_ok && rest_ok && !KEYWORDS.contains(&s)
+}
+
+impl Dfa<(Look, u8)> {
+    /// Returns the leftmost-longest match in `input`.
+    ///
+    /// This is meant for a `Dfa` made by `Nfa::determinize_longest_anchored`. It tries starting
+    /// at each char boundary in turn, and returns the longest match that starts at the first
+    /// position where there is one. Unlike `Regex::find`, this means that `a|ab` matches all of
+    /// `"ab"`.
+    pub fn longest_match(&self, input: &str) -> Option<(usize, usize)> {
+        for start in (0..(input.len() + 1)).filter(|&i| input.is_char_boundary(i)) {
+            if let Some(end) = self.longest_match_at(input, start) {
+                return Some((start, end));
+            }
+        }
+        None
+    }
+
+    /// Returns the end of the longest match that starts at `start` (which must be a char
+    /// boundary).
+    ///
+    /// The initial state is chosen by looking at the char before `start`, so look-behinds work.
+    /// We keep going past accepting states until there are no more transitions, remembering the
+    /// furthest place where a match ended.
+    pub fn longest_match_at(&self, input: &str, start: usize) -> Option<usize> {
+        let mut state = match self.init_state(Look::before(input, start)) {
+            Some(state) => state,
+            None => return None,
+        };
+        let bytes = input.as_bytes();
+        let mut ret = None;
+
+        for pos in start..bytes.len() {
+            if *self.accept(state) == Accept::Always {
+                if let Some(&(_, look_ahead)) = self.ret(state) {
+                    // We may have needed some look-ahead before knowing that the match ended.
+                    ret = max(ret, Some(pos - look_ahead as usize));
+                }
+            }
+            match self.transitions(state).get(bytes[pos]) {
+                Some(&next) => state = next,
+                None => return ret,
+            }
+        }
+
+        match (self.accept(state), self.ret(state)) {
+            (&Accept::AtEoi, _) => Some(bytes.len()),
+            (&Accept::Always, Some(&(_, look_ahead))) =>
+                max(ret, Some(bytes.len() - look_ahead as usize)),
+            _ => ret,
+        }
+    }
+}
+
 impl<Ret: Debug> Debug for Dfa<Ret> {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         try!(f.write_fmt(format_args!("Dfa ({} states):\n", self.states.len())));
@@ -449,6 +898,7 @@ pub mod tests {
     use look::Look;
     use nfa::{Accept, Nfa, StateIdx};
     use range_map::{Range, RangeMap};
+    use std::mem::size_of;
     use std::usize;
 
     // Creates a non-backtracking dfa from a regex string.
@@ -498,6 +948,13 @@ pub mod tests {
         ret
     }
 
+    // Adds a transition from `from` to `to` on `byte`, keeping the existing transitions of `from`.
+    fn add_trans(dfa: &mut Dfa<(Look, u8)>, from: StateIdx, byte: u8, to: StateIdx) {
+        let mut trans: Vec<_> = dfa.transitions(from).ranges_values().cloned().collect();
+        trans.push((Range::new(byte, byte), to));
+        dfa.set_transitions(from, trans.into_iter().collect());
+    }
+
     #[test]
     fn test_anchored_dfa_simple() {
         let dfa = make_anchored("a");
@@ -560,6 +1017,258 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn to_rust_fn() {
+        let dfa = make_anchored("a+b");
+        let src = dfa.to_rust_fn("match_ab");
+        let init = dfa.init_at_start().unwrap();
+
+        assert!(src.starts_with("pub fn match_ab(input: &str) -> bool {"));
+        assert!(src.contains(&format!("let mut state: usize = {};", init)));
+        assert!(src.contains(&format!("({}, 97) => ", init)));
+        let num_trans: usize = (0..dfa.num_states())
+            .map(|s| dfa.transitions(s).num_ranges())
+            .sum();
+        assert_eq!(src.matches("            (").count(), num_trans);
+        assert_eq!(src.matches(" => true,").count(), 1);
+
+        let empty: Dfa<(Look, u8)> = Dfa::new();
+        assert!(empty.to_rust_fn("never").contains("false"));
+
+        let dfa = make_anchored("[a-z]");
+        let init = dfa.init_at_start().unwrap();
+        assert!(dfa.to_rust_fn("_lower").contains(&format!("({}, 97..=122) => ", init)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn to_rust_fn_bad_name() {
+        make_anchored("a").to_rust_fn("match");
+    }
+
+    #[test]
+    #[should_panic]
+    fn to_rust_fn_bad_name_chars() {
+        make_anchored("a").to_rust_fn("1 + 1");
+    }
+
+    #[test]
+    fn to_dot() {
+        let dfa = make_dfa("[a-z]+").unwrap();
+        let dot = dfa.to_dot();
+        let accepting: Vec<_> = (0..dfa.num_states())
+            .filter(|&s| *dfa.accept(s) != Accept::Never)
+            .collect();
+
+        assert!(dot.starts_with("digraph dfa {"));
+        assert_eq!(accepting.len(), 1);
+        assert_eq!(dot.matches("[shape=doublecircle]").count(), 1);
+        let acc = accepting[0];
+        assert!(dot.contains(&format!("\t{} -> {} [label=\"a-z\"];", acc, acc)));
+        assert!(dot.contains("style=bold"));
+
+        // Ranges to the same target are merged, and unprintable bytes are shown in hex.
+        let dot = make_dfa("[\"\\x00-\\x05]").unwrap().to_dot();
+        assert!(dot.contains("[label=\"0x00-0x05, \\\"\"]"));
+    }
+
+    #[test]
+    fn inspection() {
+        let dfa = make_dfa("ab").unwrap();
+        let init = dfa.init_otherwise().unwrap();
+        assert!(!dfa.is_accepting(init));
+        assert_eq!(dfa.transitions(init).num_ranges(), 1);
+
+        let mid = *dfa.transitions(init).get(b'a').unwrap();
+        assert!(dfa.transitions(init).get(b'b').is_none());
+        assert!(!dfa.is_accepting(mid));
+
+        let last = *dfa.transitions(mid).get(b'b').unwrap();
+        assert!(dfa.is_accepting(last));
+        assert_eq!(*dfa.accept(last), Accept::Always);
+        assert!(dfa.transitions(last).is_empty());
+        assert_eq!(dfa.num_states(), 3);
+    }
+
+    #[test]
+    fn anchored_start() {
+        for re in &[r"\Afoo", "^foo", r"\A(?:foo|^bar)"] {
+            let dfa = make_dfa(re).unwrap();
+            assert!(dfa.is_anchored());
+            // There is no loop at the start that would let the match begin after an 'x'.
+            let init = dfa.init_at_start().unwrap();
+            assert!(dfa.transitions(init).get(b'x').is_none());
+            assert!(dfa.transitions(init).get(b'f').is_some());
+        }
+        assert!(!make_dfa("foo").unwrap().is_anchored());
+        assert!(!make_dfa(r"\Afoo|bar").unwrap().is_anchored());
+    }
+
+    #[test]
+    fn accepts_empty() {
+        assert!(make_dfa("a*").unwrap().accepts_empty());
+        assert!(make_dfa("(foo)?").unwrap().accepts_empty());
+        assert!(make_dfa("^$").unwrap().accepts_empty());
+        assert!(!make_dfa("a+").unwrap().accepts_empty());
+        assert!(!make_dfa("a$").unwrap().accepts_empty());
+        assert!(!make_dfa(r"\b").unwrap().accepts_empty());
+        assert!(make_anchored("a*").accepts_empty());
+        assert!(!make_anchored("a").accepts_empty());
+    }
+
+    #[test]
+    fn equivalent() {
+        let unoptimized = |re: &str| {
+            Nfa::from_regex(re)
+                .unwrap()
+                .remove_looks()
+                .byte_me(usize::MAX)
+                .unwrap()
+                .determinize(usize::MAX)
+                .unwrap()
+        };
+        let ab = unoptimized("a|b");
+        let class = unoptimized("[ab]");
+        assert!(ab.num_states() != class.num_states());
+        assert!(ab.equivalent(&class));
+        assert!(class.equivalent(&ab));
+        assert!(ab.equivalent(&ab.clone().optimize()));
+
+        assert!(!ab.equivalent(&unoptimized("[abc]")));
+        assert!(!ab.equivalent(&unoptimized("a|bb")));
+        assert!(!ab.equivalent(&unoptimized("a$|b")));
+
+        // A dead state is the same as a missing transition.
+        let mut dead = class.clone();
+        let dead_idx = dead.add_state(Accept::Never, None);
+        let init = dead.init_otherwise().unwrap();
+        add_trans(&mut dead, init, b'x', dead_idx);
+        assert!(dead.equivalent(&ab));
+    }
+
+    #[test]
+    fn remove_dead_states() {
+        let abc = make_dfa("abc").unwrap();
+        assert!(abc.dead_states().is_empty());
+
+        // Add a sink that loops back to itself on every byte.
+        let mut dfa = abc.clone();
+        let sink = dfa.add_state(Accept::Never, None);
+        dfa.set_transitions(sink, Some((Range::new(0, 255), sink)).into_iter().collect());
+        let init = dfa.init_otherwise().unwrap();
+        add_trans(&mut dfa, init, b'x', sink);
+        assert_eq!(dfa.dead_states(), vec![sink]);
+
+        dfa.remove_dead_states();
+        assert_eq!(dfa.num_states(), abc.num_states());
+        assert!(dfa.dead_states().is_empty());
+        assert!(dfa.equivalent(&abc));
+        assert!(dfa.transitions(init).get(b'x').is_none());
+
+        // A dead initial state is kept, but without its transitions.
+        let mut dfa = trans_dfa_anchored(2, &[(0, 1, Range::new(b'a', b'a'))]);
+        dfa.init[Look::Full.as_usize()] = Some(0);
+        assert_eq!(dfa.dead_states(), vec![0, 1]);
+        dfa.remove_dead_states();
+        assert_eq!(dfa.num_states(), 1);
+        assert!(dfa.transitions(0).is_empty());
+        assert_eq!(dfa.init_otherwise(), Some(0));
+    }
+
+    #[test]
+    fn complete() {
+        let abc = make_dfa("abc").unwrap();
+        let mut dfa = abc.clone();
+        let dead = dfa.complete().unwrap();
+        assert_eq!(dfa.num_states(), abc.num_states() + 1);
+        for s in 0..dfa.num_states() {
+            for b in 0..256 {
+                assert!(dfa.transitions(s).get(b as u8).is_some());
+            }
+        }
+        assert_eq!(*dfa.transitions(dead).get(b'a').unwrap(), dead);
+        assert!(dfa.equivalent(&abc));
+
+        // Completing a second time doesn't do anything.
+        let completed = dfa.clone();
+        assert_eq!(dfa.complete(), None);
+        assert!(dfa == completed);
+
+        dfa.remove_dead_states();
+        assert!(dfa == abc);
+    }
+
+    #[test]
+    fn approx_memory_usage() {
+        let dfa = make_dfa("a").unwrap();
+        assert!(dfa.approx_memory_usage() >= dfa.num_states() * size_of::<State<(Look, u8)>>());
+
+        // Adding some extra ranges to the initial state makes it bigger.
+        let mut bigger = dfa.clone();
+        let size = bigger.approx_memory_usage();
+        let init = bigger.init_otherwise().unwrap();
+        add_trans(&mut bigger, init, b'x', init);
+        add_trans(&mut bigger, init, b'z', init);
+        assert!(bigger.approx_memory_usage() > size);
+    }
+
+    fn make_longest(re: &str) -> Dfa<(Look, u8)> {
+        Nfa::from_regex(re)
+            .unwrap()
+            .remove_looks()
+            .byte_me(usize::MAX)
+            .unwrap()
+            .determinize_longest_anchored(usize::MAX)
+            .unwrap()
+            .optimize()
+    }
+
+    #[test]
+    fn longest_match() {
+        let longest = |re: &str, s: &str| make_longest(re).longest_match(s);
+        // A leftmost-first engine would stop after the `a`.
+        assert_eq!(longest("a|ab", "ab"), Some((0, 2)));
+        assert_eq!(longest("a|ab", "xab"), Some((1, 3)));
+        assert_eq!(longest("a+?", "aaa"), Some((0, 3)));
+        assert_eq!(longest("x", "abc"), None);
+        assert_eq!(longest("x*", ""), Some((0, 0)));
+
+        // We keep going through non-accepting states after an accepting one, and give up once
+        // there are no more transitions.
+        assert_eq!(longest("a|abc", "abd"), Some((0, 1)));
+        assert_eq!(longest("a|abc", "abc"), Some((0, 3)));
+        // The last state accepts, but has no transitions out of it.
+        assert_eq!(longest("ab", "xabc"), Some((1, 3)));
+
+        // Looking behind at the starting position.
+        assert_eq!(longest(r"\ba+", "baa aa"), Some((4, 6)));
+        assert_eq!(longest(r"(?m)^a+", "ba\naa"), Some((3, 5)));
+        // Looking ahead at the end of the match.
+        assert_eq!(longest(r"a\b|a b", "a b"), Some((0, 3)));
+        assert_eq!(longest(r"a\b", "ab a"), Some((3, 4)));
+        assert_eq!(longest(r"a$|ab", "ab"), Some((0, 2)));
+        assert_eq!(longest(r"a$|ab", "ac a"), Some((3, 4)));
+    }
+
+    #[test]
+    fn stats() {
+        let stats = make_dfa("[a-z]+").unwrap().stats();
+        assert_eq!(stats, DfaStats {
+            num_states: 2,
+            num_ranges: 2,
+            num_targets: 1,
+            num_byte_classes: 2,
+        });
+
+        let stats = make_dfa("a[0-9]|b").unwrap().stats();
+        assert_eq!(stats, DfaStats {
+            num_states: 3,
+            num_ranges: 3,
+            num_targets: 2,
+            num_byte_classes: 4,
+        });
+    }
+
     #[test]
     fn test_class_normalized() {
         let mut re = make_dfa("[abcdw]").unwrap();
@@ -606,10 +1315,67 @@ pub mod tests {
         }
         eq!("(a|aa)", "a");
         eq!("abcd*?", "abc");
+        // Laziness only changes where a match ends, not what is matched.
+        eq!("a*?b", "a*b");
+        eq!("a+?b", "a+b");
+        eq!("(?:ab)??c", "(?:ab)?c");
+        eq!("x(?U)a*b", "xa*b");
         //eq!("a*?", ""); // TODO: figure out how empty regexes should behave
     }
 
-    // TODO: add a test checking that minimize() doesn't clobber return values.
+    #[test]
+    fn minimize_redundant() {
+        // The subset construction keeps the two branches apart, even though they're equivalent.
+        let nfa = Nfa::from_regex("ac|bc")
+            .unwrap()
+            .remove_looks()
+            .byte_me(usize::MAX)
+            .unwrap();
+        let dfa = nfa.determinize(usize::MAX).unwrap();
+        let min = dfa.minimize();
+        assert!(min.num_states() < dfa.num_states());
+        assert_eq!(min.optimize(), make_dfa("[ab]c").unwrap());
+
+        // The same thing happens with longest-match determinization.
+        let longest = |re: &str| {
+            Nfa::from_regex(re)
+                .unwrap()
+                .remove_looks()
+                .byte_me(usize::MAX)
+                .unwrap()
+                .determinize_longest(usize::MAX)
+                .unwrap()
+        };
+        let dfa = longest("a|abc|xbc");
+        let min = dfa.minimize();
+        assert!(min.num_states() < dfa.num_states());
+        assert_eq!(min.optimize(), longest("a|[ax]bc").optimize());
+    }
+
+    #[test]
+    fn minimize_keeps_rets() {
+        // States 1 and 2 look the same, except for their return values.
+        let mut dfa = trans_dfa_anchored(
+            3,
+            &[(0, 1, Range::new(b'a', b'a')), (0, 2, Range::new(b'b', b'b'))],
+        );
+        dfa.init[Look::Boundary.as_usize()] = Some(0);
+        dfa.states[1].accept = Accept::Always;
+        dfa.states[1].ret = Some((Look::Full, 0));
+        dfa.states[2].accept = Accept::Always;
+        dfa.states[2].ret = Some((Look::WordChar, 1));
+
+        let min = dfa.minimize();
+        assert_eq!(min.num_states(), 3);
+        let a = *min.transitions(min.init_at_start().unwrap()).get(b'a').unwrap();
+        let b = *min.transitions(min.init_at_start().unwrap()).get(b'b').unwrap();
+        assert_eq!(min.ret(a), Some(&(Look::Full, 0)));
+        assert_eq!(min.ret(b), Some(&(Look::WordChar, 1)));
+
+        // With the same return values, they get merged.
+        dfa.states[2].ret = Some((Look::Full, 0));
+        assert_eq!(dfa.minimize().num_states(), 2);
+    }
 
     /*
     #[test]
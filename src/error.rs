@@ -9,12 +9,19 @@
 use regex_syntax;
 use std::error;
 use std::fmt;
+use std::str::Utf8Error;
 
 #[derive(Debug)]
 pub enum Error {
     RegexSyntax(regex_syntax::Error),
     TooManyStates,
     InvalidEngine(&'static str),
+    /// Returned by `Regex::find_bytes` when the input isn't valid UTF-8 and `InvalidUtf8::Error`
+    /// was requested.
+    InvalidUtf8(Utf8Error),
+    /// Returned by `Nfa::from_text` when the text doesn't describe a valid `Nfa` of the
+    /// requested kind.
+    InvalidNfaText(String),
 }
 
 use error::Error::*;
@@ -24,6 +31,8 @@ impl fmt::Display for Error {
             RegexSyntax(ref e) => write!(f, "Regex syntax error: {}", e),
             TooManyStates => write!(f, "State overflow"),
             InvalidEngine(s) => write!(f, "Invalid engine: {}", s),
+            InvalidUtf8(ref e) => write!(f, "Invalid UTF-8: {}", e),
+            InvalidNfaText(ref s) => write!(f, "Invalid Nfa description: {}", s),
         }
     }
 }
@@ -34,6 +43,16 @@ impl error::Error for Error {
             RegexSyntax(ref e) => e.description(),
             TooManyStates => "This NFA required too many states to represent as a DFA.",
             InvalidEngine(_) => "The regex was not compatible with the requested engine.",
+            InvalidUtf8(ref e) => e.description(),
+            InvalidNfaText(_) => "The text was not a valid description of an Nfa.",
+        }
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            RegexSyntax(ref e) => Some(e),
+            InvalidUtf8(ref e) => Some(e),
+            _ => None,
         }
     }
 }
@@ -44,3 +63,26 @@ impl From<regex_syntax::Error> for Error {
     }
 }
 
+impl From<Utf8Error> for Error {
+    fn from(e: Utf8Error) -> Error {
+        InvalidUtf8(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nfa::Nfa;
+    use std::error::Error;
+
+    #[test]
+    fn display() {
+        let err = Nfa::from_regex("(abc").unwrap_err();
+        let msg = format!("{}", err);
+        assert!(msg.starts_with("Regex syntax error: "));
+        assert!(msg.len() > "Regex syntax error: ".len());
+        assert!(err.cause().is_some());
+
+        assert_eq!(format!("{}", super::Error::TooManyStates), "State overflow");
+        assert!(super::Error::TooManyStates.cause().is_none());
+    }
+}
@@ -9,8 +9,68 @@
 use dfa::{Dfa, RetTrait};
 use nfa::{Nfa, NoLooks, StateIdx};
 use num_traits::PrimInt;
+use range_map::Range;
+use std::char;
 use std::collections::HashSet;
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
+
+/// Escapes the characters that are special inside a quoted DOT label.
+pub fn dot_escape(s: &str) -> String {
+    let mut ret = String::new();
+    for c in s.chars() {
+        if c == '"' || c == '\\' {
+            ret.push('\\');
+        }
+        ret.push(c);
+    }
+    ret
+}
+
+/// Tokens that know how to show themselves in the edge labels of DOT output.
+pub trait DotLabel: Copy + PartialEq {
+    fn dot_label(self) -> String;
+}
+
+impl DotLabel for u8 {
+    /// Printable ASCII bytes are shown as characters, and other bytes in hex.
+    fn dot_label(self) -> String {
+        if self > b' ' && self <= b'~' {
+            (self as char).to_string()
+        } else {
+            format!("0x{:02X}", self)
+        }
+    }
+}
+
+impl DotLabel for u32 {
+    /// Printable chars are shown as themselves, and everything else as a code point.
+    fn dot_label(self) -> String {
+        match char::from_u32(self) {
+            Some(c) if !c.is_control() && !c.is_whitespace() => c.to_string(),
+            _ => format!("U+{:04X}", self),
+        }
+    }
+}
+
+/// Returns the DOT label for a range of tokens: either a single token, or the two endpoints
+/// separated by a dash.
+pub fn range_label<Tok: DotLabel>(range: Range<Tok>) -> String {
+    if range.start == range.end {
+        range.start.dot_label()
+    } else {
+        format!("{}-{}", range.start.dot_label(), range.end.dot_label())
+    }
+}
+
+/// Builds a `String` by running `write` on an empty one.
+///
+/// Writing to a `String` never fails, so this lets `write` use `try!` instead of unwrapping every
+/// write.
+pub fn write_to_string<F: FnOnce(&mut String) -> fmt::Result>(write: F) -> String {
+    let mut ret = String::new();
+    write(&mut ret).expect("writing to a String failed");
+    ret
+}
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum DfsInstruction {
@@ -183,7 +243,18 @@ impl<Tok: Debug + PrimInt> Graph for Nfa<Tok, NoLooks> {
 #[cfg(test)]
 mod tests {
     use dfa::tests::make_dfa;
-    use graph::Graph;
+    use graph::{range_label, Graph};
+    use range_map::Range;
+
+    #[test]
+    fn range_labels() {
+        assert_eq!(range_label(Range::new(b'a', b'a')), "a");
+        assert_eq!(range_label(Range::new(b'a', b'z')), "a-z");
+        assert_eq!(range_label(Range::new(0u8, b' ')), "0x00-0x20");
+        assert_eq!(range_label(Range::new('é' as u32, 'é' as u32)), "é");
+        assert_eq!(range_label(Range::new(0u32, 9)), "U+0000-U+0009");
+        assert_eq!(range_label(Range::new(0xD800u32, 0x10FFFF)), "U+D800-U+10FFFF");
+    }
 
     #[test]
     fn cycles() {
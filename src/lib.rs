@@ -21,6 +21,23 @@ assert_eq!(re.find("My birthday is 1986-08-22!"), Some((15, 25)));
 The most useful function in this crate is `Regex::find`, which looks for the first substring of the
 given string that match the language of the DFA.
 
+# Working with the automata directly
+
+The `Nfa` and `Dfa` types that `Regex` is built from are also exported, for when you want to
+inspect or transform the automata yourself (for example, to print them with `to_dot`, or to check
+whether two regexes are `equivalent`). The usual pipeline is `Nfa::from_regex`, then
+`remove_looks`, then `byte_me`, then one of the `determinize_*` methods.
+
+```rust
+use regex_dfa::Nfa;
+let dfa = Nfa::from_regex("a+b").unwrap()
+    .remove_looks()
+    .byte_me(1000).unwrap()
+    .determinize(1000).unwrap()
+    .optimize();
+println!("{}", dfa.to_dot());
+```
+
 # Comparison to the `regex` crate
 
 Compared to rust's standard `regex` crate, the main feature of `regex_dfa` is that `regex_dfa`
@@ -91,6 +108,9 @@ mod regex;
 mod runner;
 mod unicode;
 
+pub use dfa::{Dfa, DfaStats, RetTrait};
 pub use error::Error;
-pub use regex::Regex;
+pub use look::Look;
+pub use nfa::{Accept, CaptureMap, Flags, HasLooks, Lookability, Nfa, NoLooks, StateIdx, Tag};
+pub use regex::{InvalidUtf8, MatchSemantics, Matches, OverlappingMatches, Regex};
 pub type Result<T> = ::std::result::Result<T, Error>;
@@ -12,6 +12,8 @@ use range_map::{Range, RangeSet};
 use std::cmp::Ordering;
 use unicode::PERLW;
 
+/// A condition on a single char of input (or on the start or end of the input), used to describe
+/// look-ahead and look-behind.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Ord)]
 pub enum Look {
     Full,
@@ -81,6 +83,17 @@ impl Look {
         }
     }
 
+    /// Returns the most specific `Look` that describes the char before `pos` in `s`, or
+    /// `Look::Boundary` if `pos` is zero.
+    pub fn before(s: &str, pos: usize) -> Look {
+        match s[..pos].chars().next_back() {
+            None => Look::Boundary,
+            Some('\n') => Look::NewLine,
+            Some(c) if Look::WordChar.as_set().contains(c as u32) => Look::WordChar,
+            Some(_) => Look::NotWordChar,
+        }
+    }
+
     pub fn supersets(&self) -> Vec<Look> {
         ALL.iter().cloned().filter(|x| *self <= *x).collect()
     }
@@ -130,13 +130,14 @@
 //! non-consuming transition leads to an accepting state, it means that the source of that
 //! transition should become a conditionally accepting state.
 
+use error::Error;
 use look::Look;
-use nfa::{Accept, HasLooks, LookPair, Nfa, NoLooks, StateIdx};
+use nfa::{Accept, CaptureMap, Flags, HasLooks, LookPair, Nfa, NoLooks, StateIdx, Tag};
 use std::cmp::max;
 use std::collections::HashSet;
 use std::ops::Deref;
 use range_map::{Range, RangeSet};
-use regex_syntax::{CharClass, ClassRange, Expr, Repeater};
+use regex_syntax::{CharClass, ClassRange, Expr, ExprBuilder, Repeater};
 
 // Converts a `CharClass` into a `RangeSet`
 fn class_to_set(cc: &CharClass) -> RangeSet<u32> {
@@ -162,20 +163,201 @@ impl Nfa<u32, HasLooks> {
                         .all(|(idx, s)| s.consuming.ranges_values().all(|&(_, val)| val == idx + 1)));
     }
 
+    /// Creates an `Nfa` from a description that was made by `to_text`.
+    ///
+    /// As well as being well-formed, the description must satisfy the invariants of an
+    /// `Nfa<u32, HasLooks>`: there are no `init` lines, only the last state accepts, and every
+    /// consuming transition goes from a state to the one after it.
+    pub fn from_text(text: &str) -> ::Result<Nfa<u32, HasLooks>> {
+        let ret: Nfa<u32, HasLooks> = try!(Nfa::parse_text(text));
+        match ret.text_invariant_error() {
+            Some(msg) => Err(Error::InvalidNfaText(msg.to_owned())),
+            None => Ok(ret),
+        }
+    }
+
+    // Like `check_invariants`, but for an `Nfa` that came from `from_text`: instead of panicking,
+    // returns a description of the first invariant that doesn't hold.
+    fn text_invariant_error(&self) -> Option<&'static str> {
+        if self.states.is_empty() {
+            return Some("there must be at least one state");
+        }
+        if !self.init.is_empty() {
+            return Some("there cannot be any explicit initial states");
+        }
+
+        let last = self.states.len() - 1;
+        for (idx, st) in self.states.iter().enumerate() {
+            let accept = if idx == last { Accept::Always } else { Accept::Never };
+            if st.accept != accept {
+                return Some("the last state must accept, and no others");
+            }
+            if st.accept_state != idx || st.accept_look != Look::Full || st.accept_tokens != 0 {
+                return Some("states cannot accept after look-ahead");
+            }
+            if !st.looking.is_empty() && !st.consuming.is_empty() {
+                return Some("a state cannot have both look and consuming transitions");
+            }
+            if st.consuming.ranges_values().any(|&(_, tgt)| tgt != idx + 1) {
+                return Some("consuming transitions must go to the next state");
+            }
+        }
+        None
+    }
+
     /// Creates a new Nfa from a regex string.
     pub fn from_regex(re: &str) -> ::Result<Nfa<u32, HasLooks>> {
+        Nfa::from_regex_with_flags(re, Flags::default())
+    }
+
+    /// Creates a new Nfa from a regex string, parsing it with the given flags.
+    ///
+    /// The flags only set the defaults: they can still be overridden by flags in the regex (e.g.
+    /// `(?-i)`).
+    pub fn from_regex_with_flags(re: &str, flags: Flags) -> ::Result<Nfa<u32, HasLooks>> {
+        let expr = try!(ExprBuilder::new()
+            .case_insensitive(flags.case_insensitive)
+            .multi_line(flags.multi_line)
+            .dot_matches_new_line(flags.dot_matches_new_line)
+            .swap_greed(flags.swap_greed)
+            .parse(re));
+        Ok(Nfa::from_expr(&expr))
+    }
+
+    /// Creates a new Nfa from a regex that was already parsed by `regex_syntax`.
+    pub fn from_expr(expr: &Expr) -> Nfa<u32, HasLooks> {
+        Nfa::from_parsed(expr, &mut None)
+    }
+
+    /// Creates a new Nfa from a regex string, keeping track of its capture groups.
+    ///
+    /// Every capture group is delimited by a pair of eps transitions in the returned `Nfa`; the
+    /// `CaptureMap` says which ones. Note that the tags refer to the states of the returned
+    /// `Nfa<u32, HasLooks>`, and they are not carried through `remove_looks`.
+    pub fn from_regex_with_captures(re: &str) -> ::Result<(Nfa<u32, HasLooks>, CaptureMap)> {
         let expr = try!(Expr::parse(re));
+        let mut caps = Some(CaptureMap::new());
+        let ret = Nfa::from_parsed(&expr, &mut caps);
+        // The unwrap is ok because nobody takes the map out of the Option.
+        Ok((ret, caps.unwrap()))
+    }
+
+    // Builds an Nfa from a parsed regex, recording capture groups if `caps` is not `None`.
+    fn from_parsed(expr: &Expr, caps: &mut Option<CaptureMap>) -> Nfa<u32, HasLooks> {
         let mut ret = Nfa::new();
 
         ret.add_state(Accept::Never);
-        ret.add_expr(&expr);
+        ret.add_expr(expr, caps);
         ret.add_eps(0, 1);
 
         let len = ret.num_states();
         ret.states[len - 1].accept = Accept::Always;
 
         ret.check_invariants();
-        Ok(ret)
+        ret
+    }
+
+    /// Returns an `Nfa` that matches anything matched by `self` followed by anything matched by
+    /// `other`.
+    ///
+    /// Both `self` and `other` should have been made by `from_regex` (or by one of the other
+    /// combinators).
+    pub fn concat(mut self, other: Nfa<u32, HasLooks>) -> Nfa<u32, HasLooks> {
+        let self_end = self.num_states() - 1;
+        self.states[self_end].accept = Accept::Never;
+        let other_start = self.append_shifted(other);
+        self.add_eps(self_end, other_start);
+
+        self.check_invariants();
+        self
+    }
+
+    /// Returns an `Nfa` that matches anything matched by either `self` or `other`.
+    ///
+    /// Matches of `self` have priority over matches of `other`.
+    pub fn union(self, other: Nfa<u32, HasLooks>) -> Nfa<u32, HasLooks> {
+        let mut ret = Nfa::with_capacity(self.num_states() + other.num_states() + 2);
+        ret.add_state(Accept::Never);
+        let mut ends = Vec::with_capacity(2);
+        for nfa in vec![self, other] {
+            let start = ret.append_shifted(nfa);
+            let end = ret.num_states() - 1;
+            ret.states[end].accept = Accept::Never;
+            ret.add_eps(0, start);
+            ends.push(end);
+        }
+
+        let final_idx = ret.add_state(Accept::Always);
+        for end in ends {
+            ret.add_eps(end, final_idx);
+        }
+
+        ret.check_invariants();
+        ret
+    }
+
+    /// Returns an `Nfa` that matches zero or more repetitions of `self`.
+    ///
+    /// The repetition is greedy, like `*` (and unlike `*?`).
+    pub fn star(self) -> Nfa<u32, HasLooks> {
+        let mut ret = Nfa::with_capacity(self.num_states() + 2);
+        ret.add_state(Accept::Never);
+        let start = ret.append_shifted(self);
+        let end = ret.num_states() - 1;
+        ret.states[end].accept = Accept::Never;
+        let final_idx = ret.add_state(Accept::Always);
+
+        ret.add_alt_eps(0, start, final_idx, true);
+        ret.add_alt_eps(end, start, final_idx, true);
+
+        ret.check_invariants();
+        ret
+    }
+
+    // Appends all of the states in `other` to `self`, and returns the index that `other`'s initial
+    // state ended up with.
+    fn append_shifted(&mut self, other: Nfa<u32, HasLooks>) -> StateIdx {
+        let offset = self.num_states();
+        for mut st in other.states {
+            st.consuming.map_values(|x| *x + offset);
+            for look in &mut st.looking {
+                look.target_state += offset;
+            }
+            st.accept_state += offset;
+            self.states.push(st);
+        }
+        offset
+    }
+
+    /// Returns true if no accepting state can be reached from the initial state.
+    ///
+    /// This is cheaper than determinizing just to find out that the language is empty. It only
+    /// ignores look transitions that can never be traversed on their own, so it may return false
+    /// for an `Nfa` whose language is empty because of a combination of looks (e.g. `\b\B`).
+    pub fn is_empty_language(&self) -> bool {
+        if self.states.is_empty() {
+            return true;
+        }
+
+        let mut visited = vec![false; self.states.len()];
+        let mut stack = vec![0];
+        visited[0] = true;
+        while let Some(s) = stack.pop() {
+            if self.states[s].accept != Accept::Never {
+                return false;
+            }
+
+            let st = &self.states[s];
+            let consuming = st.consuming.ranges_values().map(|&(_, t)| t);
+            let looking = st.looking.iter().filter(|l| !l.is_empty()).map(|l| l.target_state);
+            for t in consuming.chain(looking) {
+                if !visited[t] {
+                    visited[t] = true;
+                    stack.push(t);
+                }
+            }
+        }
+        true
     }
 
     /// Adds a non-input consuming transition between states `source` and `target`.
@@ -348,14 +530,14 @@ impl Nfa<u32, HasLooks> {
     }
 
     /// Appends a sequence of states that recognizes the concatenation of `exprs`.
-    fn add_concat_exprs(&mut self, exprs: &[Expr]) {
+    fn add_concat_exprs(&mut self, exprs: &[Expr], caps: &mut Option<CaptureMap>) {
         if let Some((expr, rest)) = exprs.split_first() {
-            self.add_expr(expr);
+            self.add_expr(expr, caps);
 
             for expr in rest {
                 let cur_len = self.num_states();
                 self.add_eps(cur_len - 1, cur_len);
-                self.add_expr(expr);
+                self.add_expr(expr, caps);
             }
         } else {
             self.add_state(Accept::Never);
@@ -365,7 +547,7 @@ impl Nfa<u32, HasLooks> {
     /// Appends a sequence of states that recognizes one of the expressions in `alts`.
     ///
     /// The earlier expressions in `alts` get higher priority when matching.
-    fn add_alternate_exprs(&mut self, alts: &[Expr]) {
+    fn add_alternate_exprs(&mut self, alts: &[Expr], caps: &mut Option<CaptureMap>) {
         // Add the new initial state that feeds into the alternate.
         let init_idx = self.num_states();
         self.add_state(Accept::Never);
@@ -374,7 +556,7 @@ impl Nfa<u32, HasLooks> {
         for expr in alts {
             let expr_init_idx = self.states.len();
             self.add_eps(init_idx, expr_init_idx);
-            self.add_expr(expr);
+            self.add_expr(expr, caps);
             expr_end_indices.push(self.states.len() - 1);
         }
 
@@ -387,43 +569,45 @@ impl Nfa<u32, HasLooks> {
     }
 
     /// Appends new states, representing multiple copies of `expr`.
-    fn add_repeat(&mut self, expr: &Expr, rep: Repeater, greedy: bool) {
+    fn add_repeat(&mut self, expr: &Expr, rep: Repeater, greedy: bool,
+                  caps: &mut Option<CaptureMap>) {
         match rep {
             Repeater::ZeroOrOne => {
-                self.add_repeat_up_to(expr, 1, greedy);
+                self.add_repeat_up_to(expr, 1, greedy, caps);
             },
             Repeater::ZeroOrMore => {
-                self.add_repeat_zero_or_more(expr, greedy);
+                self.add_repeat_zero_or_more(expr, greedy, caps);
             },
             Repeater::OneOrMore => {
-                self.add_repeat_min_max(expr, 1, None, greedy);
+                self.add_repeat_min_max(expr, 1, None, greedy, caps);
             },
             Repeater::Range{ min, max } => {
-                self.add_repeat_min_max(expr, min, max, greedy);
+                self.add_repeat_min_max(expr, min, max, greedy, caps);
             }
         }
     }
 
     /// Repeats `expr` a fixed number of times (which must be positive).
-    fn add_repeat_exact(&mut self, expr: &Expr, n: u32) {
+    fn add_repeat_exact(&mut self, expr: &Expr, n: u32, caps: &mut Option<CaptureMap>) {
         assert!(n > 0);
-        self.add_expr(expr);
+        self.add_expr(expr, caps);
         for _ in 1..n {
             let idx = self.states.len();
-            self.add_expr(expr);
+            self.add_expr(expr, caps);
             self.add_eps(idx - 1, idx);
         }
     }
 
     /// Repeats `expr` between zero and `n` times (`n` must be positive).
-    fn add_repeat_up_to(&mut self, expr: &Expr, n: u32, greedy: bool) {
+    fn add_repeat_up_to(&mut self, expr: &Expr, n: u32, greedy: bool,
+                        caps: &mut Option<CaptureMap>) {
         assert!(n > 0);
 
         self.add_state(Accept::Never);
         let mut init_indices = Vec::<StateIdx>::with_capacity(n as usize);
         for _ in 0..n {
             init_indices.push(self.states.len() as StateIdx);
-            self.add_expr(expr);
+            self.add_expr(expr, caps);
         }
         let final_idx = self.states.len() - 1;
         for idx in init_indices {
@@ -447,7 +631,8 @@ impl Nfa<u32, HasLooks> {
     ///
     /// The new states represent a language that accepts at least `min` and at most `maybe_max`
     /// copies of `expr`. (If `maybe_max` is `None`, there is no upper bound.)
-    fn add_repeat_min_max(&mut self, expr: &Expr, min: u32, maybe_max: Option<u32>, greedy: bool) {
+    fn add_repeat_min_max(&mut self, expr: &Expr, min: u32, maybe_max: Option<u32>, greedy: bool,
+                          caps: &mut Option<CaptureMap>) {
         if min == 0 && maybe_max == Some(0) {
             // We add a state anyway, in order to maintain the convention that every expr should
             // add at least one state (otherwise keeping track of indices becomes much more
@@ -457,7 +642,7 @@ impl Nfa<u32, HasLooks> {
         }
 
         if min > 0 {
-            self.add_repeat_exact(expr, min);
+            self.add_repeat_exact(expr, min, caps);
 
             // If anything else comes after this, we need to connect the two parts.
             if maybe_max != Some(min) {
@@ -468,18 +653,19 @@ impl Nfa<u32, HasLooks> {
 
         if let Some(max) = maybe_max {
             if max > min {
-                self.add_repeat_up_to(expr, max - min, greedy);
+                self.add_repeat_up_to(expr, max - min, greedy, caps);
             }
         } else {
-            self.add_repeat_zero_or_more(expr, greedy);
+            self.add_repeat_zero_or_more(expr, greedy, caps);
         }
     }
 
     /// Repeats the given expression zero or more times.
-    fn add_repeat_zero_or_more(&mut self, expr: &Expr, greedy: bool) {
+    fn add_repeat_zero_or_more(&mut self, expr: &Expr, greedy: bool,
+                               caps: &mut Option<CaptureMap>) {
         let start_idx = self.num_states();
         self.add_state(Accept::Never);
-        self.add_expr(expr);
+        self.add_expr(expr, caps);
         self.add_state(Accept::Never);
         let end_idx = self.num_states() - 1;
 
@@ -487,6 +673,25 @@ impl Nfa<u32, HasLooks> {
         self.add_alt_eps(end_idx - 1, start_idx + 1, end_idx, greedy);
     }
 
+    /// Appends new states representing the capture group `group`, which contains `expr`.
+    ///
+    /// The group is delimited by two eps transitions, which are recorded as tags in `caps`.
+    fn add_group(&mut self, expr: &Expr, group: usize, caps: &mut Option<CaptureMap>) {
+        let start_idx = self.add_state(Accept::Never);
+        self.add_eps(start_idx, start_idx + 1);
+        if let Some(ref mut c) = *caps {
+            c.add_tag(Tag { group: group, start: true, source: start_idx, target: start_idx + 1 });
+        }
+
+        self.add_expr(expr, caps);
+        let end_idx = self.num_states() - 1;
+        self.add_state(Accept::Never);
+        self.add_eps(end_idx, end_idx + 1);
+        if let Some(ref mut c) = *caps {
+            c.add_tag(Tag { group: group, start: false, source: end_idx, target: end_idx + 1 });
+        }
+    }
+
     /// Adds two new states, with a look connecting them.
     fn add_look_pair(&mut self, behind: Look, ahead: Look) {
         let idx = self.add_state(Accept::Never);
@@ -504,7 +709,7 @@ impl Nfa<u32, HasLooks> {
     ///
     /// This maintains the invariant that the last state is always empty (i.e. it doesn't have any
     /// transitions leading out of it). It is also guaranteed to add at least one new state.
-    fn add_expr(&mut self, expr: &Expr) {
+    fn add_expr(&mut self, expr: &Expr, caps: &mut Option<CaptureMap>) {
         use regex_syntax::Expr::*;
 
         match *expr {
@@ -512,11 +717,11 @@ impl Nfa<u32, HasLooks> {
             Class(ref c) => self.add_single_transition(&class_to_set(c)),
             AnyChar => self.add_single_transition(&RangeSet::full()),
             AnyCharNoNL => {
-                let nls = b"\n\r".into_iter().map(|b| *b as u32);
-                self.add_single_transition(&RangeSet::except(nls))
+                let nl = Some('\n' as u32).into_iter();
+                self.add_single_transition(&RangeSet::except(nl))
             },
-            Concat(ref es) => self.add_concat_exprs(es),
-            Alternate(ref es) => self.add_alternate_exprs(es),
+            Concat(ref es) => self.add_concat_exprs(es, caps),
+            Alternate(ref es) => self.add_alternate_exprs(es, caps),
             Literal { ref chars, casei } => self.add_literal(chars.iter(), casei),
             StartLine => self.add_look_pair(Look::NewLine, Look::Full),
             StartText => self.add_look_pair(Look::Boundary, Look::Full),
@@ -530,11 +735,11 @@ impl Nfa<u32, HasLooks> {
                 self.add_look_pair(Look::WordChar, Look::WordChar);
                 self.extra_look(Look::NotWordChar, Look::NotWordChar);
             },
-            Repeat { ref e, r, greedy } => self.add_repeat(e, r, greedy),
+            Repeat { ref e, r, greedy } => self.add_repeat(e, r, greedy, caps),
 
-            // We don't support capture groups, so there is no need to keep track of
-            // the group name or number.
-            Group { ref e, .. } => self.add_expr(e),
+            // Capture groups only need special treatment if someone is keeping track of them.
+            Group { ref e, i: Some(i), .. } if caps.is_some() => self.add_group(e, i, caps),
+            Group { ref e, .. } => self.add_expr(e, caps),
 
         }
     }
@@ -542,9 +747,14 @@ impl Nfa<u32, HasLooks> {
 
 #[cfg(test)]
 mod tests {
+    use dfa::Dfa;
     use look::Look;
-    use nfa::{Accept, NoLooks, Nfa, StateIdx};
+    use nfa::{Accept, Flags, HasLooks, NoLooks, Nfa, StateIdx};
     use nfa::tests::{re_nfa, trans_nfa};
+    use range_map::Range;
+    use regex_syntax::Expr;
+    use std::collections::HashSet;
+    use std::usize;
 
     // Creates an Nfa with the given transitions, with initial state zero, and with the final
     // state the only accepting state.
@@ -677,5 +887,139 @@ mod tests {
     fn empty() {
         assert_eq!(re_nfa(""), trans_nfa_extra(1, &[]));
     }
+
+    #[test]
+    fn flags() {
+        let ci = |s: &str| Nfa::from_regex_with_flags(s, Flags::case_insensitive()).unwrap();
+        assert_eq!(dfa(ci("abc")), dfa(re("(?i)abc")));
+        assert!(ci("abc").remove_looks().is_match("xABCx"));
+        assert!(!ci("(?-i)abc").remove_looks().is_match("xABCx"));
+
+        let ml = Nfa::from_regex_with_flags("^a$", Flags::multi_line()).unwrap();
+        assert_eq!(dfa(ml), dfa(re("(?m)^a$")));
+
+        let all = Flags {
+            case_insensitive: false,
+            multi_line: false,
+            dot_matches_new_line: true,
+            swap_greed: true,
+        };
+        let nfa = Nfa::from_regex_with_flags(".*", all).unwrap();
+        assert_eq!(nfa, re("(?sU).*"));
+        assert!(!re(".").remove_looks().is_match("\n"));
+        assert!(Nfa::from_regex_with_flags(".", all).unwrap().remove_looks().is_match("\n"));
+    }
+
+    #[test]
+    fn from_expr() {
+        for s in &["", "a|b", r"(?i)\bfoo$", "[a-z]*?x{2,3}"] {
+            let expr = Expr::parse(s).unwrap();
+            assert_eq!(Nfa::from_expr(&expr), re(s));
+        }
+    }
+
+    #[test]
+    fn dot_new_line() {
+        let dot_nl = Flags { dot_matches_new_line: true, .. Flags::default() };
+        let dot = Nfa::from_regex_with_flags("^.$", Flags::default()).unwrap().remove_looks();
+        assert!(dot.is_match("a"));
+        assert!(dot.is_match("\r"));
+        assert!(!dot.is_match("\n"));
+
+        let dot = Nfa::from_regex_with_flags("^.$", dot_nl).unwrap().remove_looks();
+        assert!(dot.is_match("a"));
+        assert!(dot.is_match("\r"));
+        assert!(dot.is_match("\n"));
+        let no_dot = Nfa::from_regex_with_flags("^(?-s).$", dot_nl).unwrap().remove_looks();
+        assert!(!no_dot.is_match("\n"));
+    }
+
+    #[test]
+    fn is_empty_language() {
+        assert!(!re("[a-z]").is_empty_language());
+        assert!(!re("").is_empty_language());
+        assert!(!re(r"a\b").is_empty_language());
+
+        // The accepting state is there, but there's no way to reach it.
+        let mut nfa: Nfa<u32, HasLooks> = Nfa::new();
+        nfa.add_state(Accept::Never);
+        nfa.add_state(Accept::Never);
+        nfa.add_state(Accept::Always);
+        nfa.add_transition(0, 1, Range::new('a' as u32, 'a' as u32));
+        assert!(nfa.is_empty_language());
+
+        // The only way there is through an empty look.
+        nfa.add_look(1, 2, Look::Empty, Look::Full);
+        assert!(nfa.is_empty_language());
+        nfa.add_look(1, 2, Look::WordChar, Look::Full);
+        assert!(!nfa.is_empty_language());
+    }
+
+    #[test]
+    fn captures() {
+        let (nfa, caps) = Nfa::from_regex_with_captures("(a)(b)").unwrap();
+        assert_eq!(caps.num_groups(), 2);
+        assert_eq!(caps.tags().len(), 4);
+        assert_eq!(caps.group_tags(1), vec![0, 1]);
+        assert_eq!(caps.group_tags(2), vec![2, 3]);
+        for (i, tag) in caps.tags().iter().enumerate() {
+            assert_eq!(tag.start, i % 2 == 0);
+            assert!(nfa.states[tag.source].looking.iter()
+                    .any(|l| l.target_state == tag.target && l.behind == Look::Full));
+        }
+        let edges: HashSet<_> = caps.tags().iter().map(|t| (t.source, t.target)).collect();
+        assert_eq!(edges.len(), 4);
+
+        // The tags don't change the language.
+        assert_eq!(dfa(nfa), dfa(re("(a)(b)")));
+
+        // Non-capturing groups have no tags, and repeated groups have several.
+        assert_eq!(Nfa::from_regex_with_captures("(?:a)b").unwrap().1.num_groups(), 0);
+        let (nfa, caps) = Nfa::from_regex_with_captures("(a){3}|(b)").unwrap();
+        assert_eq!(caps.group_tags(1).len(), 6);
+        assert_eq!(caps.group_tags(2).len(), 2);
+        assert_eq!(dfa(nfa), dfa(re("(a){3}|(b)")));
+    }
+
+    fn dfa(nfa: Nfa<u32, HasLooks>) -> Dfa<(Look, u8)> {
+        nfa.remove_looks()
+            .byte_me(usize::MAX).unwrap()
+            .determinize(usize::MAX).unwrap()
+            .optimize()
+    }
+
+    fn re(s: &str) -> Nfa<u32, HasLooks> {
+        Nfa::from_regex(s).unwrap()
+    }
+
+    #[test]
+    fn accepting_states() {
+        let nfa = re("a|b");
+        assert_eq!(nfa.num_accepting(), 1);
+        assert_eq!(nfa.accepting_states(), vec![nfa.num_states() - 1]);
+
+        let nfa = re("a").union(re("b"));
+        assert_eq!(nfa.num_accepting(), 1);
+        assert_eq!(nfa.accepting_states(), vec![nfa.num_states() - 1]);
+
+        let dfa = dfa(nfa);
+        assert_eq!((0..dfa.num_states()).filter(|&s| dfa.is_accepting(s)).count(), 1);
+
+        let mut nfa: Nfa<u32, HasLooks> = Nfa::new();
+        nfa.add_state(Accept::Always);
+        nfa.add_state(Accept::Never);
+        nfa.add_state(Accept::AtEoi);
+        assert_eq!(nfa.num_accepting(), 2);
+        assert_eq!(nfa.accepting_states(), vec![0, 2]);
+    }
+
+    #[test]
+    fn combinators() {
+        assert_eq!(dfa(re("ab").concat(re("c|d"))), dfa(re("ab(c|d)")));
+        assert_eq!(dfa(re("a").union(re("b+"))), dfa(re("a|b+")));
+        assert_eq!(dfa(re("ab").star()), dfa(re("(ab)*")));
+        assert_eq!(dfa(re("a").union(re(r"\bb")).star().concat(re("c$"))),
+                   dfa(re(r"(a|\bb)*c$")));
+    }
 }
 
@@ -6,11 +6,14 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use error::Error;
+use graph::{dot_escape, range_label, write_to_string, DotLabel};
 use look::Look;
 use num_traits::PrimInt;
 use range_map::{Range, RangeMultiMap};
-use std::fmt::{self, Debug, Formatter};
+use std::fmt::{self, Debug, Formatter, Write};
 use std::marker::PhantomData;
+use std::mem::size_of;
 
 mod has_looks;
 mod no_looks;
@@ -61,6 +64,85 @@ pub enum Accept {
     Always,
 }
 
+/// Options that change how a regex is parsed, corresponding to the `i`, `m`, `s` and `U` flags.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Flags {
+    /// Letters match both their upper- and lower-case versions.
+    pub case_insensitive: bool,
+    /// `^` and `$` also match at the beginning and end of lines.
+    pub multi_line: bool,
+    /// `.` also matches `\n`.
+    pub dot_matches_new_line: bool,
+    /// Repetitions are lazy by default, and greedy if they are followed by `?`.
+    pub swap_greed: bool,
+}
+
+impl Flags {
+    /// Returns the default flags, with only case-insensitivity turned on.
+    pub fn case_insensitive() -> Flags {
+        Flags { case_insensitive: true, .. Flags::default() }
+    }
+
+    /// Returns the default flags, with only multi-line mode turned on.
+    pub fn multi_line() -> Flags {
+        Flags { multi_line: true, .. Flags::default() }
+    }
+}
+
+/// One end of a capture group in an `Nfa`.
+///
+/// Each time a capture group appears in an `Nfa`, it is delimited by a pair of eps transitions:
+/// passing through the first one means entering the group and passing through the second one means
+/// leaving it. A `Tag` records one of these transitions.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Tag {
+    /// The index of the capture group (as given by `regex_syntax`, so the first one is 1).
+    pub group: usize,
+    /// Is this the transition that enters the group (as opposed to the one leaving it)?
+    pub start: bool,
+    /// The source state of the eps transition.
+    pub source: StateIdx,
+    /// The target state of the eps transition.
+    pub target: StateIdx,
+}
+
+/// Describes where the capture groups of a regex ended up in an `Nfa`.
+///
+/// Tag indices are positions in `tags()`. A group can have more than one pair of tags, because
+/// repetitions like `(a){3}` make several copies of it.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CaptureMap {
+    tags: Vec<Tag>,
+}
+
+impl CaptureMap {
+    pub fn new() -> CaptureMap {
+        CaptureMap::default()
+    }
+
+    fn add_tag(&mut self, tag: Tag) {
+        self.tags.push(tag);
+    }
+
+    /// Returns all of the tags, in the order that they were added to the `Nfa`.
+    pub fn tags(&self) -> &[Tag] {
+        &self.tags
+    }
+
+    /// Returns the number of capture groups (not including the implicit group 0).
+    pub fn num_groups(&self) -> usize {
+        self.tags.iter().map(|t| t.group).max().unwrap_or(0)
+    }
+
+    /// Returns the indices of the tags belonging to the given group.
+    pub fn group_tags(&self, group: usize) -> Vec<usize> {
+        self.tags.iter().enumerate()
+            .filter(|&(_, t)| t.group == group)
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
 #[derive(Clone, Eq, PartialEq)]
 struct State<Tok> {
     accept: Accept,
@@ -233,6 +315,34 @@ impl<Tok: Debug + PrimInt, L: Lookability> Nfa<Tok, L> {
         self.states.len()
     }
 
+    /// Returns a rough estimate of the number of bytes of memory (stack and heap) used by me.
+    pub fn approx_memory_usage(&self) -> usize {
+        let trans_size = size_of::<(Range<Tok>, StateIdx)>();
+        let states: usize = self.states.iter()
+            .map(|s| s.consuming.num_ranges() * trans_size
+                 + s.looking.capacity() * size_of::<LookPair>())
+            .sum();
+
+        size_of::<Nfa<Tok, L>>()
+            + self.states.capacity() * size_of::<State<Tok>>()
+            + self.init.capacity() * size_of::<(Look, StateIdx)>()
+            + states
+    }
+
+    /// Returns the number of states that accept, at least at the end of the input.
+    pub fn num_accepting(&self) -> usize {
+        self.states.iter().filter(|s| s.accept != Accept::Never).count()
+    }
+
+    /// Returns the indices of the states that accept, at least at the end of the input.
+    pub fn accepting_states(&self) -> Vec<StateIdx> {
+        self.states.iter()
+            .enumerate()
+            .filter(|&(_, s)| s.accept != Accept::Never)
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
     // You've just done some operation that has changed state indices (probably by deleting
     // un-needed states). Now re-label the existing transitions according to the new state indices.
     fn map_states<F>(&mut self, map: F) where F: Fn(StateIdx) -> Option<StateIdx> {
@@ -274,6 +384,159 @@ impl<Tok: Debug + PrimInt, L: Lookability> Nfa<Tok, L> {
     pub fn is_empty(&self) -> bool {
         self.states.is_empty()
     }
+
+    /// Returns a line-oriented description of this `Nfa`, which can be turned back into an
+    /// identical `Nfa` by `from_text`.
+    ///
+    /// There is one line for each state (in order), each initial state, each consuming
+    /// transition and each look transition:
+    ///
+    /// ```text
+    /// state <accept> <accept_state> <accept_look> <accept_tokens>
+    /// init <look> <state>
+    /// consume <source> <target> <range start> <range end>
+    /// look <source> <target> <behind> <ahead>
+    /// ```
+    ///
+    /// Transitions out of each state are listed in order of priority.
+    pub fn to_text(&self) -> String {
+        write_to_string(|ret| {
+            for st in &self.states {
+                try!(writeln!(ret, "state {:?} {} {:?} {}",
+                              st.accept, st.accept_state, st.accept_look, st.accept_tokens));
+            }
+            for &(look, idx) in &self.init {
+                try!(writeln!(ret, "init {:?} {}", look, idx));
+            }
+            for (idx, st) in self.states.iter().enumerate() {
+                for &(range, target) in st.consuming.ranges_values() {
+                    try!(writeln!(ret, "consume {} {} {:?} {:?}",
+                                  idx, target, range.start, range.end));
+                }
+                for look in &st.looking {
+                    try!(writeln!(ret, "look {} {} {:?} {:?}",
+                                  idx, look.target_state, look.behind, look.ahead));
+                }
+            }
+            Ok(())
+        })
+    }
+
+    // Parses a description that was made by `to_text`, without checking any of the invariants that
+    // are specific to `L`. Each variant's `from_text` is responsible for that.
+    fn parse_text(text: &str) -> ::Result<Nfa<Tok, L>> {
+        let mut ret = Nfa::new();
+        for (line_idx, line) in text.lines().enumerate() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if !words.is_empty() && ret.add_text_line(&words).is_none() {
+                return Err(Error::InvalidNfaText(format!("line {}: {:?}", line_idx + 1, line)));
+            }
+        }
+
+        let num_states = ret.num_states();
+        if ret.states.iter().any(|st| st.accept_state >= num_states) {
+            return Err(Error::InvalidNfaText("accept_state out of range".to_owned()));
+        }
+        Ok(ret)
+    }
+
+    // Adds the state or transition described by one line of `to_text`'s output. Returns `None`
+    // if the line is invalid.
+    fn add_text_line(&mut self, words: &[&str]) -> Option<()> {
+        macro_rules! try_opt {
+            ($e:expr) => (match $e { Some(x) => x, None => return None })
+        }
+        fn parse_look(s: &str) -> Option<Look> {
+            Look::all().iter().cloned().find(|look| format!("{:?}", look) == s)
+        }
+        fn parse_accept(s: &str) -> Option<Accept> {
+            [Accept::Never, Accept::AtEoi, Accept::Always].iter()
+                .cloned()
+                .find(|acc| format!("{:?}", acc) == s)
+        }
+
+        let num_states = self.num_states();
+        let state = |s: &str| s.parse::<StateIdx>().ok().and_then(|idx| {
+            if idx < num_states { Some(idx) } else { None }
+        });
+        let tok = |s: &str| Tok::from_str_radix(s, 10).ok();
+
+        match (words[0], words.len()) {
+            ("state", 5) => {
+                let accept_state = try_opt!(words[2].parse::<StateIdx>().ok());
+                self.states.push(State {
+                    accept: try_opt!(parse_accept(words[1])),
+                    accept_state: accept_state,
+                    accept_look: try_opt!(parse_look(words[3])),
+                    accept_tokens: try_opt!(words[4].parse::<u8>().ok()),
+                    consuming: RangeMultiMap::new(),
+                    looking: Vec::new(),
+                });
+            },
+            ("init", 3) => {
+                let look = try_opt!(parse_look(words[1]));
+                self.init.push((look, try_opt!(state(words[2]))));
+            },
+            ("consume", 5) => {
+                let (source, target) = (try_opt!(state(words[1])), try_opt!(state(words[2])));
+                let (start, end) = (try_opt!(tok(words[3])), try_opt!(tok(words[4])));
+                if start > end {
+                    return None;
+                }
+                self.add_transition(source, target, Range::new(start, end));
+            },
+            ("look", 5) => {
+                let (source, target) = (try_opt!(state(words[1])), try_opt!(state(words[2])));
+                self.states[source].looking.push(LookPair {
+                    behind: try_opt!(parse_look(words[3])),
+                    ahead: try_opt!(parse_look(words[4])),
+                    target_state: target,
+                });
+            },
+            _ => return None,
+        }
+        Some(())
+    }
+}
+
+impl<Tok: Debug + DotLabel + PrimInt, L: Lookability> Nfa<Tok, L> {
+    /// Returns a description of this `Nfa` in Graphviz's DOT format.
+    ///
+    /// Accepting states are drawn with a double circle, and the initial states are pointed to by
+    /// edges from an extra point-shaped node (labelled by the look-behind they require).
+    /// Consuming transitions are labelled with their range of tokens (in the same way as
+    /// `Dfa::to_dot` for bytes, and as chars for `Nfa<u32, _>`), and look transitions are dashed
+    /// and labelled with their look-behind and look-ahead.
+    pub fn to_dot(&self) -> String {
+        write_to_string(|ret| {
+            ret.push_str("digraph nfa {\n\trankdir=LR;\n");
+            if !self.init.is_empty() {
+                ret.push_str("\tinit [shape=point];\n");
+            }
+            for (idx, st) in self.states.iter().enumerate() {
+                let shape = if st.accept == Accept::Never { "circle" } else { "doublecircle" };
+                try!(writeln!(ret, "\t{} [shape={}];", idx, shape));
+            }
+            for &(look, idx) in &self.init {
+                let label = format!("{:?}", look);
+                try!(writeln!(ret, "\tinit -> {} [label=\"{}\"];", idx, dot_escape(&label)));
+            }
+            for (idx, st) in self.states.iter().enumerate() {
+                for &(range, target) in st.consuming.ranges_values() {
+                    let label = range_label(range);
+                    try!(writeln!(ret, "\t{} -> {} [label=\"{}\"];",
+                                  idx, target, dot_escape(&label)));
+                }
+                for look in &st.looking {
+                    let label = format!("({:?},{:?})", look.behind, look.ahead);
+                    try!(writeln!(ret, "\t{} -> {} [label=\"{}\", style=dashed];",
+                                  idx, look.target_state, dot_escape(&label)));
+                }
+            }
+            ret.push_str("}\n");
+            Ok(())
+        })
+    }
 }
 
 impl<Tok: Debug + PrimInt, L: Lookability> Debug for Nfa<Tok, L> {
@@ -317,10 +580,13 @@ impl<Tok: Debug + PrimInt, L: Lookability> Debug for Nfa<Tok, L> {
 
 #[cfg(test)]
 pub mod tests {
-    use nfa::{Accept, NoLooks, Nfa, StateIdx};
+    use look::Look;
+    use nfa::{Accept, HasLooks, NoLooks, Nfa, StateIdx};
     use num_traits::PrimInt;
     use range_map::Range;
     use std::fmt::Debug;
+    use std::mem::size_of;
+    use std::usize;
 
     // Creates an Nfa from a regular expression string.
     pub fn re_nfa(re: &str) -> Nfa<u32, NoLooks> {
@@ -356,5 +622,99 @@ pub mod tests {
             .collect();
         trans_range_nfa(size, &range_trans)
     }
+
+    #[test]
+    fn approx_memory_usage() {
+        let mut nfa: Nfa<u32, NoLooks> = trans_nfa(3, &[(0, 1, 'a'), (1, 2, 'b')]);
+        let size = nfa.approx_memory_usage();
+        assert!(size > 3 * size_of::<(Range<u32>, StateIdx)>());
+
+        nfa.add_transition(0, 2, Range::new('x' as u32, 'x' as u32));
+        nfa.add_transition(0, 2, Range::new('z' as u32, 'z' as u32));
+        assert!(nfa.approx_memory_usage() > size);
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let nfa = Nfa::from_regex(r"(?m)^a\b[x-zé]*?$").unwrap();
+        assert_eq!(Nfa::<u32, HasLooks>::from_text(&nfa.to_text()).unwrap(), nfa);
+
+        // This one has initial states and look-ahead states.
+        let nfa = nfa.remove_looks();
+        assert!(nfa.to_text().contains("init "));
+        assert_eq!(Nfa::<u32, NoLooks>::from_text(&nfa.to_text()).unwrap(), nfa);
+
+        let nfa = nfa.byte_me(usize::MAX).unwrap();
+        assert_eq!(Nfa::<u8, NoLooks>::from_text(&nfa.to_text()).unwrap(), nfa);
+
+        let empty: Nfa<u8, NoLooks> = Nfa::new();
+        assert_eq!(Nfa::<u8, NoLooks>::from_text(&empty.to_text()).unwrap(), empty);
+    }
+
+    #[test]
+    fn text_errors() {
+        let parse = |s: &str| Nfa::<u8, NoLooks>::from_text(s);
+        assert!(parse("state Never 0 Full 0\nconsume 0 0 1 2\n").is_ok());
+        assert!(parse("state Sometimes 0 Full 0\n").is_err());
+        assert!(parse("state Never 0 Full 0\nconsume 0 1 1 2\n").is_err());
+        assert!(parse("state Never 0 Full 0\nconsume 0 0 2 1\n").is_err());
+        assert!(parse("state Never 0 Full 0\nconsume 0 0 1 256\n").is_err());
+        assert!(parse("state Never 1 Full 0\n").is_err());
+        assert!(parse("init Full 0\n").is_err());
+        assert!(parse("state Never 0 Full\n").is_err());
+        // NoLooks automata don't have look transitions.
+        assert!(parse("state Never 0 Full 0\nlook 0 0 Full Full\n").is_err());
+    }
+
+    #[test]
+    fn text_errors_has_looks() {
+        let parse = |s: &str| Nfa::<u32, HasLooks>::from_text(s);
+        let ok = "state Never 0 Full 0\nstate Always 1 Full 0\nconsume 0 1 97 97\n";
+        assert!(parse(ok).is_ok());
+        assert!(parse("state Never 0 Full 0\nstate Always 1 Full 0\nlook 0 1 Full Full\n").is_ok());
+
+        // There are no explicit initial states.
+        assert!(parse(&format!("{}init Full 0\n", ok)).is_err());
+        // Consuming transitions go to the next state.
+        assert!(parse(&format!("{}consume 1 0 97 97\n", ok)).is_err());
+        assert!(parse("state Never 0 Full 0\nstate Always 1 Full 0\nconsume 0 0 97 97\n").is_err());
+        // Only the last state accepts, and never after look-ahead.
+        assert!(parse("state Always 0 Full 0\nstate Always 1 Full 0\n").is_err());
+        assert!(parse("state Never 0 Full 0\nstate AtEoi 1 Boundary 0\n").is_err());
+        assert!(parse("state Never 0 Full 0\nstate Always 0 WordChar 1\n").is_err());
+        // No state both consumes and looks.
+        assert!(parse(&format!("{}look 0 1 Full Full\n", ok)).is_err());
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn to_dot() {
+        let mut nfa: Nfa<u32, NoLooks> = trans_nfa(3, &[(0, 1, 'a'), (1, 2, 'b'), (0, 2, 'c')]);
+        nfa.add_transition(2, 2, Range::new(0, 9));
+        nfa.init.push((Look::Full, 0));
+        nfa.states[2].accept = Accept::Always;
+        let dot = nfa.to_dot();
+
+        assert!(dot.starts_with("digraph nfa {"));
+        assert_eq!(dot.matches("[shape=circle]").count(), 2);
+        assert_eq!(dot.matches("[shape=doublecircle]").count(), 1);
+        assert_eq!(dot.matches(" -> ").count(), 5);
+        assert!(dot.contains("init -> 0 [label=\"Full\"];"));
+        assert!(dot.contains("0 -> 1 [label=\"a\"];"));
+        assert!(dot.contains("2 -> 2 [label=\"U+0000-U+0009\"];"));
+
+        // Bytes are labelled like in `Dfa::to_dot`.
+        let mut nfa: Nfa<u8, NoLooks> = Nfa::new();
+        nfa.add_state(Accept::Never);
+        nfa.add_state(Accept::Always);
+        nfa.add_transition(0, 1, Range::new(b'a', b'z'));
+        nfa.add_transition(1, 1, Range::new(0, 0x1F));
+        let dot = nfa.to_dot();
+        assert!(dot.contains("0 -> 1 [label=\"a-z\"];"));
+        assert!(dot.contains("1 -> 1 [label=\"0x00-0x1F\"];"));
+
+        let dot = Nfa::from_regex(r"a\b").unwrap().to_dot();
+        assert!(dot.contains("style=dashed"));
+    }
 }
 
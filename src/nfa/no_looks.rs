@@ -12,7 +12,7 @@ use itertools::Itertools;
 use look::Look;
 use nfa::{Accept, Nfa, NoLooks, State, StateIdx, StateSet};
 use num_traits::PrimInt;
-use range_map::{Range, RangeMap, RangeMultiMap};
+use range_map::{Range, RangeMap, RangeMultiMap, RangeSet};
 use std::{char, u8, usize};
 use std::cmp::max;
 use std::collections::{HashMap, HashSet};
@@ -167,6 +167,17 @@ lazy_static! {
 }
 
 impl<Tok: Debug + PrimInt> Nfa<Tok, NoLooks> {
+    /// Creates an `Nfa` from a description that was made by `to_text`.
+    ///
+    /// The description must not contain any `look` lines.
+    pub fn from_text(text: &str) -> ::Result<Nfa<Tok, NoLooks>> {
+        let ret: Nfa<Tok, NoLooks> = try!(Nfa::parse_text(text));
+        if ret.states.iter().any(|st| !st.looking.is_empty()) {
+            return Err(Error::InvalidNfaText("look transitions need HasLooks".to_owned()));
+        }
+        Ok(ret)
+    }
+
     // Returns the set of all states that can be reached from some initial state.
     fn reachable_from<I>(&self, states: I) -> HashSet<StateIdx> where I: Iterator<Item=StateIdx> {
         let mut active: HashSet<StateIdx> = states.collect();
@@ -219,14 +230,26 @@ impl<Tok: Debug + PrimInt> Nfa<Tok, NoLooks> {
     /// and all states that cannot lead to an accepting state.
     pub fn trim_unreachable(&mut self) {
         let reachable = self.reachable_states();
+        self.retain_states(&reachable);
+    }
+
+    /// Removes all states that cannot be reached from an initial state.
+    ///
+    /// Unlike `trim_unreachable`, this keeps states that can't lead to an accepting state.
+    pub fn remove_unreachable(&mut self) {
+        let reachable = self.reachable_from(self.init.iter().map(|pair| pair.1));
+        self.retain_states(&reachable);
+    }
 
+    // Deletes all the states that aren't in `keep`, and renumbers the remaining ones.
+    fn retain_states(&mut self, keep: &HashSet<StateIdx>) {
         let mut old_states = Vec::new();
         swap(&mut self.states, &mut old_states);
         let mut old_to_new = vec![None; old_states.len()];
 
         let (new_to_old, new_states): (Vec<_>, Vec<State<Tok>>) = old_states.into_iter()
             .enumerate()
-            .filter(|&(i, _)| reachable.contains(&i))
+            .filter(|&(i, _)| keep.contains(&i))
             .unzip();
         self.states = new_states;
 
@@ -269,6 +292,114 @@ impl Nfa<u32, NoLooks> {
         }
         Ok(ret)
     }
+
+    /// Adds a transition from `source` to `target` that consumes `ch`.
+    pub fn add_char(&mut self, source: StateIdx, target: StateIdx, ch: char) {
+        self.add_transition(source, target, Range::new(ch as u32, ch as u32));
+    }
+
+    /// Adds transitions from `source` to `target` that consume any char in `set`.
+    pub fn add_char_class(&mut self, source: StateIdx, target: StateIdx, set: &RangeSet<u32>) {
+        for range in set.ranges() {
+            self.add_transition(source, target, range);
+        }
+    }
+
+    /// Adds a chain of new states that consumes `s`, starting from `source`.
+    ///
+    /// Returns the index of the last state in the chain (or `source`, if `s` is empty). The new
+    /// states are all non-accepting.
+    pub fn add_string(&mut self, source: StateIdx, s: &str) -> StateIdx {
+        let mut cur = source;
+        for ch in s.chars() {
+            let next = self.add_state(Accept::Never);
+            self.add_char(cur, next, ch);
+            cur = next;
+        }
+        cur
+    }
+
+    /// Returns true if this `Nfa` matches anywhere in `input`.
+    ///
+    /// See `shortest_match` for the caveats.
+    pub fn is_match(&self, input: &str) -> bool {
+        self.shortest_match(input).is_some()
+    }
+
+    /// Runs this `Nfa` directly on `input`, returning the (byte) position where the earliest-ending
+    /// match ends.
+    ///
+    /// This simulates the automaton one `char` at a time, without determinizing it. It is much
+    /// slower than running a `Dfa`, but it is handy for testing the other transformations. As
+    /// usual, a match may start anywhere in the input (it is as though the `Nfa` had an implicit
+    /// `.*` at the beginning); use an anchored regex if that isn't what you want.
+    ///
+    /// Look-around is only supported through the `NoLooks` representation, so an `Nfa` built by
+    /// `from_regex` must go through `remove_looks` before it can be run.
+    pub fn shortest_match(&self, input: &str) -> Option<usize> {
+        let mut active: Vec<StateIdx> = Vec::new();
+        let mut next: Vec<StateIdx> = Vec::new();
+        let mut is_active = vec![false; self.states.len()];
+        let mut prev: Option<char> = None;
+
+        // We also visit the position at the end of the input (with no char there), in order to
+        // check for matches that end at the end of the input.
+        let positions = input.char_indices()
+            .map(|(pos, ch)| (pos, Some(ch)))
+            .chain(Some((input.len(), None)).into_iter());
+
+        for (pos, ch) in positions {
+            // Try to start a new match here. Which initial states are allowed depends on the
+            // previous char.
+            for &(look, st_idx) in &self.init {
+                let allowed = match prev {
+                    Some(p) => look.as_set().contains(p as u32),
+                    None => look.allows_eoi(),
+                };
+                if allowed && !is_active[st_idx] {
+                    is_active[st_idx] = true;
+                    active.push(st_idx);
+                }
+            }
+
+            // A state with look-ahead accepts only after consuming the look-ahead char, so its
+            // match actually ended one char ago.
+            let prev_len = prev.map_or(0, |p| p.len_utf8());
+            let end = active.iter()
+                .filter_map(|&s| {
+                    let st = &self.states[s];
+                    match st.accept {
+                        Accept::Always if st.accept_tokens > 0 => Some(pos - prev_len),
+                        Accept::Always => Some(pos),
+                        Accept::AtEoi if ch.is_none() => Some(pos),
+                        _ => None,
+                    }
+                })
+                .min();
+            if end.is_some() {
+                return end;
+            }
+
+            if let Some(c) = ch {
+                for &s in &active {
+                    is_active[s] = false;
+                }
+                next.clear();
+                for &s in &active {
+                    for &(range, tgt) in self.states[s].consuming.ranges_values() {
+                        if range.contains(c as u32) && !is_active[tgt] {
+                            is_active[tgt] = true;
+                            next.push(tgt);
+                        }
+                    }
+                }
+                swap(&mut active, &mut next);
+            }
+            prev = ch;
+        }
+
+        None
+    }
 }
 
 impl Nfa<u8, NoLooks> {
@@ -286,6 +417,112 @@ impl Nfa<u8, NoLooks> {
         Determinizer::determinize(self, max_states, MatchChoice::LongestMatch, self.init.clone())
     }
 
+    /// Converts this `Nfa` into a `Dfa` that finds the longest match starting at a given position.
+    ///
+    /// The returned `Dfa` only matches starting from the position where it starts running. It has
+    /// initial states for `Look::Boundary` (for starting at the beginning of the input) and for
+    /// `Look::WordChar`, `Look::NotWordChar` and `Look::NewLine` (for starting after a char of that
+    /// kind), so look-behinds still work at the starting position. See `Dfa::longest_match`.
+    pub fn determinize_longest_anchored(&self, max_states: usize) -> ::Result<Dfa<(Look, u8)>> {
+        let mut init = Vec::new();
+        for &look in &[Look::Boundary, Look::WordChar, Look::NotWordChar, Look::NewLine] {
+            init.extend(self.init.iter().filter(|pair| look <= pair.0).map(|pair| (look, pair.1)));
+        }
+        Determinizer::determinize(self, max_states, MatchChoice::LongestMatch, init)
+    }
+
+    /// Returns an equivalent `Nfa` with as few states as possible.
+    ///
+    /// This is done by determinizing, minimizing the resulting `Dfa` and then converting it back
+    /// into an `Nfa`. There is at most one initial state for each `Look`, and apart from the
+    /// look-ahead paths described below, every state has at most one transition for each byte.
+    ///
+    /// A state that accepts after some look-ahead needs to remember which state the look-ahead
+    /// started from (so that the result can still be passed to `reverse`). Since a `Dfa` state can
+    /// be reached by look-ahead from many different states, each of these gets its own copy of the
+    /// look-ahead paths, ending in its own accepting state.
+    pub fn minimized(&self, max_states: usize) -> ::Result<Nfa<u8, NoLooks>> {
+        let dfa = try!(self.determinize(max_states)).optimize();
+        let mut ret = Nfa::with_capacity(dfa.num_states());
+
+        for idx in 0..dfa.num_states() {
+            ret.add_state(*dfa.accept(idx));
+            for &(range, target) in dfa.transitions(idx).ranges_values() {
+                ret.add_transition(idx, target, range);
+            }
+        }
+        for idx in 0..dfa.num_states() {
+            match dfa.ret(idx) {
+                Some(&(look, tokens)) if tokens > 0 => {
+                    ret.states[idx].accept = Accept::Never;
+                    try!(ret.add_look_ahead_paths(&dfa, idx, look, tokens, max_states));
+                },
+                Some(&(look, _)) => ret.states[idx].accept_look = look,
+                None => {},
+            }
+        }
+        for &look in Look::all() {
+            if let Some(idx) = dfa.init_state(look) {
+                ret.init.push((look, idx));
+            }
+        }
+        Ok(ret)
+    }
+
+    // Helper for `minimized`: `dfa_state` accepts after `tokens` bytes of look-ahead.
+    //
+    // For every state `start` that can reach `dfa_state` in exactly `tokens` steps, adds a copy of
+    // all those paths, ending in a new look-ahead state whose `accept_state` is `start`. This
+    // assumes that the states of `self` up to `dfa.num_states()` are copies of the states of `dfa`.
+    fn add_look_ahead_paths(&mut self, dfa: &Dfa<(Look, u8)>, dfa_state: StateIdx, look: Look,
+                            tokens: u8, max_states: usize) -> ::Result<()> {
+        let tokens_len = tokens as usize;
+        // `reaches[d][s]` is true if state `s` can reach `dfa_state` in exactly `d` steps.
+        let mut reaches = vec![vec![false; dfa.num_states()]; tokens_len + 1];
+        reaches[0][dfa_state] = true;
+        for d in 1..(tokens_len + 1) {
+            for s in 0..dfa.num_states() {
+                let r = dfa.transitions(s).ranges_values().any(|&(_, t)| reaches[d - 1][t]);
+                reaches[d][s] = r;
+            }
+        }
+
+        for start in (0..dfa.num_states()).filter(|&s| reaches[tokens_len][s]) {
+            // The `Dfa` states that we can be in after `d` steps, and their copies.
+            let mut layer = vec![(start, start)];
+            for d in 1..(tokens_len + 1) {
+                let mut next: Vec<(StateIdx, StateIdx)> = Vec::new();
+                for &(dfa_src, src) in &layer {
+                    for &(range, dfa_tgt) in dfa.transitions(dfa_src).ranges_values() {
+                        if !reaches[tokens_len - d][dfa_tgt] {
+                            continue;
+                        }
+                        let existing = next.iter().find(|x| x.0 == dfa_tgt).map(|x| x.1);
+                        let tgt = match existing {
+                            Some(tgt) => tgt,
+                            None => {
+                                let tgt = if d == tokens_len {
+                                    self.add_look_ahead_state(look, tokens, start)
+                                } else {
+                                    self.add_state(Accept::Never)
+                                };
+                                next.push((dfa_tgt, tgt));
+                                tgt
+                            },
+                        };
+                        self.add_transition(src, tgt, range);
+                    }
+                }
+                layer = next;
+            }
+
+            if self.num_states() > max_states {
+                return Err(Error::TooManyStates);
+            }
+        }
+        Ok(())
+    }
+
     /// Returns the reversal of this `Nfa`.
     ///
     /// If `self` matches some string of bytes, then the return value of this method will match
@@ -356,6 +593,55 @@ impl Nfa<u8, NoLooks> {
     /// The result is actually a little bit different, because `.` matches a whole code point,
     /// whereas the `^.*` that we add works at the byte level.
     pub fn anchor(mut self, max_states: usize) -> ::Result<Nfa<u8, NoLooks>> {
+        try!(self.add_anchor_states(max_states));
+        self.trim_unreachable();
+        Ok(self)
+    }
+
+    /// Like `anchor`, but the returned `Nfa` can also start searching in the middle of the input.
+    ///
+    /// Besides the initial state for `Look::Boundary`, the returned `Nfa` has initial states for
+    /// `Look::WordChar`, `Look::NotWordChar` and `Look::NewLine`. To start searching at some
+    /// position after the beginning of the input, use the one that matches the char before that
+    /// position (`NewLine` if it is `'\n'`, and `NotWordChar` for the other non-word chars). Every
+    /// match found that way starts at or after the position where the search started.
+    pub fn anchor_resumable(mut self, max_states: usize) -> ::Result<Nfa<u8, NoLooks>> {
+        let init = self.init.clone();
+        let loop_state = try!(self.add_anchor_states(max_states));
+
+        for &look in &[Look::WordChar, Look::NotWordChar, Look::NewLine] {
+            let allowed: StateSet = init.iter()
+                .filter(|pair| look <= pair.0)
+                .map(|pair| pair.1)
+                .collect();
+            let accept = self.accept_union(&allowed);
+            let resume_state = self.add_state(accept);
+
+            // This is like the loop state, except that since we know what the previous char was,
+            // the matches that need to look behind at it can start immediately.
+            for &st_idx in &allowed {
+                for &(range, target) in self.states[st_idx].consuming.clone().ranges_values() {
+                    self.add_transition(resume_state, target, range);
+                }
+                if self.states[st_idx].accept == Accept::Always {
+                    break;
+                }
+            }
+            if accept != Accept::Always {
+                for &(range, target) in self.states[loop_state].consuming.clone().ranges_values() {
+                    self.add_transition(resume_state, target, range);
+                }
+            }
+            self.init.push((look, resume_state));
+        }
+
+        self.trim_unreachable();
+        Ok(self)
+    }
+
+    // Does the work for `anchor`, except for trimming the unreachable states. Returns the index of
+    // the state that loops over the input before the match starts.
+    fn add_anchor_states(&mut self, max_states: usize) -> ::Result<StateIdx> {
         let loop_accept = self.init_accept(Look::Full);
         let loop_state = self.add_state(loop_accept);
         let init_accept = self.init_accept(Look::Boundary);
@@ -421,8 +707,7 @@ impl Nfa<u8, NoLooks> {
         // The new Nfa is only allowed to start at the beginning of the input, and only at the new
         // initial state.
         self.init.push((Look::Boundary, init_state));
-        self.trim_unreachable();
-        Ok(self)
+        Ok(loop_state)
     }
 
     // This does the same thing as add_utf8_sequences, but it gets the transitions from a dfa,
@@ -768,6 +1053,142 @@ mod tests {
         assert_eq!(nfa, target);
     }
 
+    #[test]
+    fn shortest_match() {
+        assert!(re_nfa("abc").is_match("xxabcxx"));
+        assert!(!re_nfa("abc").is_match("xxabxx"));
+        assert_eq!(re_nfa("a+").shortest_match("baaa"), Some(2));
+        assert_eq!(re_nfa("").shortest_match("abc"), Some(0));
+        assert_eq!(re_nfa("^a").shortest_match("ba"), None);
+        assert_eq!(re_nfa("a$").shortest_match("aab"), None);
+        assert_eq!(re_nfa("a$").shortest_match("baa"), Some(3));
+        assert_eq!(re_nfa(r"a\b").shortest_match("aa a"), Some(2));
+        assert_eq!(re_nfa(r"\ba").shortest_match("ba a"), Some(4));
+        assert_eq!(re_nfa("é").shortest_match("aéb"), Some(3));
+    }
+
+    #[test]
+    fn trim_unreachable() {
+        // State 3 can't be reached, states 4 and 5 can't reach an accepting state.
+        let mut nfa: Nfa<u32, NoLooks> = trans_nfa(6, &[(0, 1, 'a'),
+                                                        (1, 2, 'b'),
+                                                        (3, 2, 'c'),
+                                                        (1, 4, 'd'),
+                                                        (4, 5, 'e')]);
+        nfa.init.push((Look::Full, 0));
+        nfa.states[2].accept = Accept::Always;
+        let inputs = ["ab", "xaby", "cb", "c", "ad", "ade", "a"];
+        let before: Vec<_> = inputs.iter().map(|s| nfa.is_match(s)).collect();
+
+        nfa.trim_unreachable();
+        assert_eq!(nfa.num_states(), 3);
+        let after: Vec<_> = inputs.iter().map(|s| nfa.is_match(s)).collect();
+        assert_eq!(before, after);
+        assert_eq!(after, vec![true, true, false, false, false, false, false]);
+    }
+
+    #[test]
+    fn determinize_reproducible() {
+        for re in &["a|b|c", r"\bfoo\b|bar$", "[a-z]+[0-9]*", "(?m)^x+y?$", "αβ|[δ-ω]+"] {
+            let dfa = re_dfa(re);
+            for _ in 0..10 {
+                // The numbering of the states should be exactly the same every time, even after
+                // minimization (which internally iterates over hash sets).
+                assert_eq!(re_dfa(re), dfa);
+                assert_eq!(re_dfa(re).optimize(), dfa.clone().optimize());
+            }
+        }
+    }
+
+    #[test]
+    fn builders() {
+        let mut nfa: Nfa<u32, NoLooks> = Nfa::new();
+        nfa.add_state(Accept::Never);
+        nfa.init.push((Look::Full, 0));
+        let end = nfa.add_string(0, "abc");
+        nfa.states[end].accept = Accept::Always;
+
+        assert_eq!(nfa.num_states(), 4);
+        assert!(nfa.is_match("xabcx"));
+        assert!(!nfa.is_match("abx"));
+        let dfa = nfa.byte_me(usize::MAX).unwrap().determinize(usize::MAX).unwrap();
+        assert_eq!(dfa.optimize(), re_dfa("abc").optimize());
+
+        let mut nfa: Nfa<u32, NoLooks> = Nfa::new();
+        nfa.add_state(Accept::Never);
+        nfa.add_state(Accept::Always);
+        nfa.init.push((Look::Full, 0));
+        assert_eq!(nfa.add_string(0, ""), 0);
+        nfa.add_char(0, 1, 'é');
+        nfa.add_char_class(0, 1, &"xz".chars().map(|c| Range::new(c as u32, c as u32)).collect());
+        assert!(nfa.is_match("é"));
+        assert!(nfa.is_match("z"));
+        assert!(!nfa.is_match("y"));
+    }
+
+    #[test]
+    fn minimized() {
+        for re in &["a|b", "(a|ab)(c|bcd)", r"\bx*y\b", "(?m)^[0-9]+$", "é+|e"] {
+            let nfa = re_nfa(re).byte_me(usize::MAX).unwrap();
+            let min = nfa.minimized(usize::MAX).unwrap();
+            let dfa = nfa.determinize(usize::MAX).unwrap();
+            let min_dfa = min.determinize(usize::MAX).unwrap();
+            assert!(dfa.equivalent(&min_dfa));
+        }
+
+        // Without look-ahead, there is one state for each state of the minimal `Dfa`.
+        for re in &["a|b", "(a|ab)(c|bcd)", "é+|e"] {
+            let nfa = re_nfa(re).byte_me(usize::MAX).unwrap();
+            let min = nfa.minimized(usize::MAX).unwrap();
+            let dfa = nfa.determinize(usize::MAX).unwrap().optimize();
+            assert_eq!(min.num_states(), dfa.num_states());
+        }
+    }
+
+    #[test]
+    fn minimized_look_ahead() {
+        // Every look-ahead state can be reached from its `accept_state` in exactly `accept_tokens`
+        // steps.
+        for re in &[r"\bx*y\b", "(?m)^[0-9]+$", r"a\b"] {
+            let min = re_nfa(re).byte_me(usize::MAX).unwrap().minimized(usize::MAX).unwrap();
+            let look_aheads: Vec<_> = (0..min.num_states())
+                .filter(|&i| min.states[i].accept_tokens > 0)
+                .collect();
+            assert!(!look_aheads.is_empty());
+
+            for &idx in &look_aheads {
+                let mut layer = vec![min.states[idx].accept_state];
+                for _ in 0..min.states[idx].accept_tokens {
+                    layer = layer.iter()
+                        .flat_map(|&s| min.states[s].consuming.ranges_values().map(|x| x.1))
+                        .collect();
+                }
+                assert!(layer.contains(&idx));
+            }
+        }
+    }
+
+    #[test]
+    fn remove_unreachable() {
+        // States 2 and 3 are an island; state 4 is reachable but can't accept.
+        let mut nfa: Nfa<u32, NoLooks> = trans_nfa(5, &[(0, 1, 'a'), (2, 3, 'b'), (0, 4, 'c')]);
+        nfa.init.push((Look::Full, 0));
+        nfa.states[1].accept = Accept::Always;
+        nfa.states[3].accept = Accept::Always;
+        let inputs = ["a", "b", "c", "ba"];
+        let before: Vec<_> = inputs.iter().map(|s| nfa.is_match(s)).collect();
+
+        let mut trimmed = nfa.clone();
+        nfa.remove_unreachable();
+        assert_eq!(nfa.num_states(), 3);
+        let after: Vec<_> = inputs.iter().map(|s| nfa.is_match(s)).collect();
+        assert_eq!(before, after);
+        assert_eq!(after, vec![true, false, false, true]);
+
+        trimmed.trim_unreachable();
+        assert_eq!(trimmed.num_states(), 2);
+    }
+
     #[test]
     fn determinize_pruning() {
         assert_eq!(re_dfa("a|aa"), re_dfa("a"));
@@ -6,17 +6,56 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use dfa::Dfa;
 use error::Error;
-use nfa::{Nfa, NoLooks};
+use look::Look;
+use nfa::{HasLooks, Nfa, NoLooks};
+use regex_syntax::Expr;
 use runner::anchored::AnchoredEngine;
 use runner::forward_backward::{ForwardBackwardEngine, Prefix};
 use runner::Engine;
 use std;
 use std::fmt::Debug;
+use std::str;
+
+/// What to do about invalid UTF-8 in the input to `Regex::find_bytes`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InvalidUtf8 {
+    /// Fail with `Error::InvalidUtf8`.
+    Error,
+    /// Never match any invalid bytes. To anchors and word boundaries, an invalid sequence looks
+    /// like a char that isn't a word char or a newline: for example, `^a` doesn't match
+    /// `b"\xffa"`, but `\ba` does. A match that would include an invalid sequence is thrown
+    /// away, and the search starts again after that sequence.
+    Skip,
+    /// Replace every invalid sequence with U+FFFD (like `String::from_utf8_lossy`), which can
+    /// then be matched like any other char.
+    Replace,
+}
+
+/// Which match `Regex::find_with_semantics` returns when several matches start at the leftmost
+/// possible position.
+///
+/// The two kinds of semantics only disagree about where a match ends, never about where it
+/// starts. For example, `a|ab` matches `(0, 1)` in `"ab"` with `LeftmostFirst`, but `(0, 2)` with
+/// `LeftmostLongest`; `a+?` matches one `a` in `"aaa"` with `LeftmostFirst`, but all three with
+/// `LeftmostLongest`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MatchSemantics {
+    /// The match that the regex prefers, like in Perl and the `regex` crate: earlier alternatives
+    /// win over later ones, and greedy (or lazy) repetitions match as much (or as little) as
+    /// possible. This is what `Regex::find` does.
+    LeftmostFirst,
+    /// The longest match, like in POSIX. The order of alternatives and the greediness of
+    /// repetitions don't matter.
+    LeftmostLongest,
+}
 
 #[derive(Debug)]
 pub struct Regex {
     engine: Box<Engine<u8>>,
+    // Finds the longest match starting at a given position, for `MatchSemantics::LeftmostLongest`.
+    longest: Dfa<(Look, u8)>,
 }
 
 // An engine that doesn't match anything.
@@ -25,6 +64,7 @@ struct EmptyEngine;
 
 impl<Ret: Debug> Engine<Ret> for EmptyEngine {
     fn find(&self, _: &str) -> Option<(usize, usize, Ret)> { None }
+    fn find_at(&self, _: &str, _: usize) -> Option<(usize, usize, Ret)> { None }
     fn clone_box(&self) -> Box<Engine<Ret>> { Box::new(EmptyEngine) }
 }
 
@@ -32,6 +72,7 @@ impl Clone for Regex {
     fn clone(&self) -> Regex {
         Regex {
             engine: self.engine.clone_box(),
+            longest: self.longest.clone(),
         }
     }
 }
@@ -45,18 +86,37 @@ impl Regex {
     /// Creates a new `Regex` from a regular expression string, but only if it doesn't require too
     /// many states.
     pub fn new_bounded(re: &str, max_states: usize) -> ::Result<Regex> {
-        let nfa = try!(Nfa::from_regex(re));
+        Regex::from_nfa(try!(Nfa::from_regex(re)), max_states)
+    }
+
+    /// Creates a new `Regex` from a regular expression that was already parsed by `regex_syntax`.
+    pub fn from_expr(expr: &Expr) -> ::Result<Regex> {
+        Regex::from_nfa(Nfa::from_expr(expr), std::usize::MAX)
+    }
+
+    fn from_nfa(nfa: Nfa<u32, HasLooks>, max_states: usize) -> ::Result<Regex> {
         let nfa = nfa.remove_looks();
 
-        let eng = if nfa.is_empty() {
-            Box::new(EmptyEngine) as Box<Engine<u8>>
-        } else if nfa.is_anchored() {
+        if nfa.is_empty() {
+            return Ok(Regex {
+                engine: Box::new(EmptyEngine),
+                longest: Dfa::new(),
+            });
+        }
+
+        let longest = try!(try!(nfa.clone().byte_me(max_states))
+            .determinize_longest_anchored(max_states))
+            .optimize();
+        let eng = if nfa.is_anchored() {
             Box::new(try!(Regex::make_anchored(nfa, max_states))) as Box<Engine<u8>>
         } else {
             Box::new(try!(Regex::make_forward_backward(nfa, max_states))) as Box<Engine<u8>>
         };
 
-        Ok(Regex { engine: eng })
+        Ok(Regex {
+            engine: eng,
+            longest: longest,
+        })
     }
 
     fn make_anchored(nfa: Nfa<u32, NoLooks>, max_states: usize)
@@ -76,7 +136,7 @@ impl Regex {
             return Err(Error::InvalidEngine("anchors rule out the forward-backward engine"));
         }
 
-        let f_nfa = try!(try!(nfa.clone().byte_me(max_states)).anchor(max_states));
+        let f_nfa = try!(try!(nfa.clone().byte_me(max_states)).anchor_resumable(max_states));
         let b_nfa = try!(try!(nfa.byte_me(max_states)).reverse(max_states));
 
         let f_dfa = try!(f_nfa.determinize(max_states)).optimize();
@@ -90,6 +150,7 @@ impl Regex {
         });
 
         let mut f_prog = f_dfa.compile();
+        let mut f_init = f_dfa.init.clone();
         let prefix = Prefix::from_parts(f_dfa.prefix_strings());
         match prefix {
             Prefix::Empty => {},
@@ -100,14 +161,22 @@ impl Regex {
                 // prefix before trying to match again.
                 let f_dfa = f_dfa.cut_loop_to_init().optimize();
                 f_prog = f_dfa.compile();
+                f_init = f_dfa.init;
             },
         }
 
-        Ok(ForwardBackwardEngine::new(f_prog, prefix, b_prog))
+        Ok(ForwardBackwardEngine::new(f_prog, f_init, prefix, b_prog))
     }
 
     /// Returns the index range of the first match, if there is a match. The indices returned are
     /// byte indices of the string. The first index is inclusive; the second is exclusive.
+    ///
+    /// Matches follow the same leftmost-first semantics as Perl and the `regex` crate: among all
+    /// the matches starting at the leftmost possible position, we return the one that the
+    /// regex prefers, where earlier alternatives are preferred to later ones and greedy (or lazy)
+    /// repetitions prefer to match as much (or as little) as possible. In particular, this is not
+    /// necessarily the longest match: `a|ab` matches only the `a` in `"ab"`, while `ab|a` matches
+    /// all of it. Use `find_with_semantics` to get the longest match instead.
     pub fn find(&self, s: &str) -> Option<(usize, usize)> {
         if let Some((start, end, look_behind)) = self.engine.find(s) {
             Some((start + look_behind as usize, end))
@@ -116,10 +185,403 @@ impl Regex {
         }
     }
 
+    /// Like `find`, but lets you choose which of the matches starting at the leftmost position
+    /// to return. `find_with_semantics(s, MatchSemantics::LeftmostFirst)` is the same as
+    /// `find(s)`.
+    pub fn find_with_semantics(&self, s: &str, semantics: MatchSemantics)
+    -> Option<(usize, usize)> {
+        match semantics {
+            MatchSemantics::LeftmostFirst => self.find(s),
+            MatchSemantics::LeftmostLongest => {
+                // Both kinds of semantics agree on where the match starts, so we let the usual
+                // engine find the start, and then look for the longest match from there.
+                self.find(s).map(|(start, end)| {
+                    (start, self.longest.longest_match_at(s, start).unwrap_or(end))
+                })
+            },
+        }
+    }
+
+    // Like `find`, but only returns matches that start at or after `pos` (which must be a char
+    // boundary). The input before `pos` is still there for look-behinds to look at.
+    fn find_at(&self, s: &str, pos: usize) -> Option<(usize, usize)> {
+        if let Some((start, end, look_behind)) = self.engine.find_at(s, pos) {
+            Some((start + look_behind as usize, end))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the index range of the first match in a slice of bytes that may not be valid
+    /// UTF-8.
+    ///
+    /// Valid UTF-8 is searched just like `find` would. `on_invalid` says what to do with anything
+    /// else. The indices returned are byte indices into `s`, even when invalid sequences were
+    /// replaced.
+    pub fn find_bytes(&self, s: &[u8], on_invalid: InvalidUtf8)
+    -> ::Result<Option<(usize, usize)>> {
+        match on_invalid {
+            InvalidUtf8::Error => Ok(self.find(try!(str::from_utf8(s)))),
+            InvalidUtf8::Skip => {
+                let (text, offsets) = replace_invalid(s);
+                // The replacement chars are the only ones whose bytes all have the same offset.
+                let is_replaced = |i: usize| offsets[i] == offsets[i + 1];
+                let mut pos = 0;
+                while let Some((start, end)) = self.find_at(&text, pos) {
+                    match (start..end).find(|&i| is_replaced(i)) {
+                        // U+FFFD is three bytes long in UTF-8.
+                        Some(i) => pos = i + 3,
+                        None => return Ok(Some((offsets[start], offsets[end]))),
+                    }
+                }
+                Ok(None)
+            },
+            InvalidUtf8::Replace => {
+                let (text, offsets) = replace_invalid(s);
+                Ok(self.find(&text).map(|(start, end)| (offsets[start], offsets[end])))
+            },
+        }
+    }
+
     pub fn is_match(&self, s: &str) -> bool {
         // TODO: for the forward-backward engine, this could be faster because we don't need
         // to run backward.
         self.find(s).is_some()
     }
+
+    /// Like `find`, but only reports a match if it ends within the first `max_bytes` bytes of `s`.
+    ///
+    /// If `max_bytes` falls in the middle of a char, it is rounded down so that the char is left
+    /// out. The search looks at most one char past that point, so that look-aheads like `$` and
+    /// `\b` see the real input there. If the match found in this way ends after the cut-off
+    /// point, nothing is reported: for example, `b+` finds nothing in the first 3 bytes of
+    /// `"aabba"`.
+    pub fn find_within(&self, s: &str, max_bytes: usize) -> Option<(usize, usize)> {
+        let mut end = std::cmp::min(max_bytes, s.len());
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        if end == s.len() {
+            return self.find(s);
+        }
+
+        match self.find(&s[..next_char(s, end)]) {
+            Some((start, match_end)) if match_end <= end => Some((start, match_end)),
+            _ => None,
+        }
+    }
+
+    /// Returns true if there is a match in the first `max_bytes` bytes of `s`.
+    ///
+    /// See `find_within` for the details.
+    pub fn is_match_within(&self, s: &str, max_bytes: usize) -> bool {
+        self.find_within(s, max_bytes).is_some()
+    }
+
+    /// Returns an iterator over all the non-overlapping matches in `s`.
+    ///
+    /// The matches are returned as byte index ranges, like in `find`. An empty match that
+    /// immediately follows the previous match is skipped.
+    ///
+    /// Each search after the first one starts where the last match ended, but look-behinds (like
+    /// `\b` or `(?m)^`) still see the input before that point, so `^` only matches once.
+    pub fn find_iter<'r, 't>(&'r self, s: &'t str) -> Matches<'r, 't> {
+        Matches {
+            re: self,
+            text: s,
+            pos: 0,
+            last_end: None,
+        }
+    }
+
+    /// Returns an iterator over all the matches in `s`, including overlapping ones.
+    ///
+    /// For every position in `s`, this yields the first match (in the sense of `find`) that
+    /// starts at or after that position, unless it was already yielded. So every match returned
+    /// by `find_iter` is also returned here, but there may be more: `aa` has one non-overlapping
+    /// match in `"aaa"`, but two overlapping ones. Note that this doesn't return every substring
+    /// matching the regex: each match is the one that `find` would prefer, and no other matches
+    /// are returned with the same starting position.
+    ///
+    /// As with `find_iter`, look-behinds still see the input before the position that each search
+    /// starts from.
+    pub fn find_overlapping_iter<'r, 't>(&'r self, s: &'t str) -> OverlappingMatches<'r, 't> {
+        OverlappingMatches {
+            re: self,
+            text: s,
+            pos: 0,
+        }
+    }
+}
+
+// Finds the longest valid UTF-8 sequence in `s` starting at `pos`. Returns the index where it ends,
+// and the index where the next valid sequence could start (or something past the end of `s`, if
+// there are no more invalid bytes).
+fn valid_prefix(s: &[u8], pos: usize) -> (usize, usize) {
+    match str::from_utf8(&s[pos..]) {
+        Ok(_) => (s.len(), s.len() + 1),
+        Err(e) => {
+            let end = pos + e.valid_up_to();
+            (end, e.error_len().map_or(s.len(), |len| end + len))
+        },
+    }
+}
+
+// Replaces every invalid sequence in `s` with U+FFFD. Also returns the offsets: the `i`th byte of
+// the new string comes from the `offsets[i]`th byte of `s`. There is one extra offset at the end,
+// for the end of the string.
+fn replace_invalid(s: &[u8]) -> (String, Vec<usize>) {
+    let mut text = String::with_capacity(s.len());
+    let mut offsets = Vec::with_capacity(s.len() + 1);
+    let mut pos = 0;
+    while pos < s.len() {
+        let (chunk_end, next) = valid_prefix(s, pos);
+        // The unwrap is ok because valid_prefix says that this part is valid.
+        text.push_str(str::from_utf8(&s[pos..chunk_end]).unwrap());
+        offsets.extend(pos..chunk_end);
+        if chunk_end < s.len() {
+            // U+FFFD is three bytes long in UTF-8.
+            text.push('\u{FFFD}');
+            offsets.extend_from_slice(&[chunk_end; 3]);
+        }
+        pos = next;
+    }
+    offsets.push(s.len());
+    (text, offsets)
+}
+
+// Returns the position of the char boundary after `pos` in `text` (or something past the end of
+// `text`, if `pos` is at the end).
+fn next_char(text: &str, pos: usize) -> usize {
+    text[pos..].chars().next().map_or(pos + 1, |c| pos + c.len_utf8())
+}
+
+/// An iterator over the non-overlapping matches of a `Regex` in a string.
+///
+/// This is created by `Regex::find_iter`.
+#[derive(Clone, Debug)]
+pub struct Matches<'r, 't> {
+    re: &'r Regex,
+    text: &'t str,
+    // Where to start the next search. If this is past the end of `text`, we're done.
+    pos: usize,
+    last_end: Option<usize>,
+}
+
+impl<'r, 't> Iterator for Matches<'r, 't> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        while self.pos <= self.text.len() {
+            let (start, end) = match self.re.find_at(self.text, self.pos) {
+                Some(m) => m,
+                None => break,
+            };
+
+            self.pos = if start == end { next_char(self.text, end) } else { end };
+            if start == end && Some(end) == self.last_end {
+                continue;
+            }
+            self.last_end = Some(end);
+            return Some((start, end));
+        }
+
+        self.pos = self.text.len() + 1;
+        None
+    }
+}
+
+/// An iterator over the possibly overlapping matches of a `Regex` in a string.
+///
+/// This is created by `Regex::find_overlapping_iter`.
+#[derive(Clone, Debug)]
+pub struct OverlappingMatches<'r, 't> {
+    re: &'r Regex,
+    text: &'t str,
+    // Where to start the next search. If this is past the end of `text`, we're done.
+    pos: usize,
+}
+
+impl<'r, 't> Iterator for OverlappingMatches<'r, 't> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        if self.pos > self.text.len() {
+            return None;
+        }
+
+        match self.re.find_at(self.text, self.pos) {
+            Some((start, end)) => {
+                self.pos = next_char(self.text, start);
+                Some((start, end))
+            },
+            None => {
+                self.pos = self.text.len() + 1;
+                None
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+    use regex_syntax::Expr;
+
+    fn find_all(re: &str, s: &str) -> Vec<(usize, usize)> {
+        Regex::new(re).unwrap().find_iter(s).collect()
+    }
+
+    #[test]
+    fn leftmost_first() {
+        let find = |re: &str, s: &str| Regex::new(re).unwrap().find(s);
+        assert_eq!(find("a|ab", "ab"), Some((0, 1)));
+        assert_eq!(find("ab|a", "ab"), Some((0, 2)));
+        assert_eq!(find("^(?:a|ab)", "ab"), Some((0, 1)));
+        assert_eq!(find("^(?:ab|a)", "ab"), Some((0, 2)));
+        assert_eq!(find("x(?:a|ab)", "xab"), Some((0, 2)));
+        assert_eq!(find("(?:a|ab)(?:c|bcd)", "abcd"), Some((0, 4)));
+        assert_eq!(find("a+?", "aaa"), Some((0, 1)));
+        // Leftmost beats longest.
+        assert_eq!(find("b|abc", "zabc"), Some((1, 4)));
+    }
+
+    #[test]
+    fn leftmost_longest() {
+        use regex::MatchSemantics::*;
+
+        let find = |re: &str, s: &str, sem| Regex::new(re).unwrap().find_with_semantics(s, sem);
+        assert_eq!(find("a|ab", "ab", LeftmostFirst), Some((0, 1)));
+        assert_eq!(find("a|ab", "ab", LeftmostLongest), Some((0, 2)));
+        assert_eq!(find("^(?:a|ab)", "ab", LeftmostFirst), Some((0, 1)));
+        assert_eq!(find("^(?:a|ab)", "ab", LeftmostLongest), Some((0, 2)));
+        assert_eq!(find("a+?", "baaa", LeftmostFirst), Some((1, 2)));
+        assert_eq!(find("a+?", "baaa", LeftmostLongest), Some((1, 4)));
+        assert_eq!(find(r"a\b|a b", "xa b", LeftmostFirst), Some((1, 2)));
+        assert_eq!(find(r"a\b|a b", "xa b", LeftmostLongest), Some((1, 4)));
+        // Leftmost still beats longest.
+        assert_eq!(find("b|abc", "zabc", LeftmostLongest), Some((1, 4)));
+        assert_eq!(find("c|abc", "zabc", LeftmostLongest), Some((1, 4)));
+        assert_eq!(find("x", "abc", LeftmostLongest), None);
+    }
+
+    #[test]
+    fn from_expr() {
+        let expr = Expr::parse(r"\d+").unwrap();
+        let re = Regex::from_expr(&expr).unwrap();
+        assert_eq!(re.find("ab123c"), Some((2, 5)));
+        assert_eq!(re.find("abc"), None);
+    }
+
+    #[test]
+    fn find_within() {
+        let re = Regex::new("b+").unwrap();
+        assert_eq!(re.find_within("aabba", 2), None);
+        assert_eq!(re.find_within("aabba", 3), None);
+        assert_eq!(re.find_within("aabba", 4), Some((2, 4)));
+        assert_eq!(re.find_within("aabba", 100), Some((2, 4)));
+        assert!(!re.is_match_within("aabba", 0));
+        assert!(re.is_match_within("aabba", 5));
+
+        // The cut-off point doesn't split the 'é'.
+        let re = Regex::new("é").unwrap();
+        assert_eq!(re.find_within("aéa", 2), None);
+        assert_eq!(re.find_within("aéa", 3), Some((1, 3)));
+
+        // Look-aheads see the input after the cut-off point.
+        let re = Regex::new("a$").unwrap();
+        assert_eq!(re.find_within("ab", 1), None);
+        assert_eq!(re.find_within("a", 1), Some((0, 1)));
+        let re = Regex::new(r"foo\b").unwrap();
+        assert_eq!(re.find_within("foobar", 3), None);
+        assert_eq!(re.find_within("foo bar", 3), Some((0, 3)));
+    }
+
+    #[test]
+    fn find_iter() {
+        assert_eq!(find_all("a+", "baaabaa"), vec![(1, 4), (5, 7)]);
+        assert_eq!(find_all("a+", "bbb"), vec![]);
+        assert_eq!(find_all("[0-9]+", "1 22 333"), vec![(0, 1), (2, 4), (5, 8)]);
+        assert_eq!(find_all("é", "aébé"), vec![(1, 3), (4, 6)]);
+    }
+
+    #[test]
+    fn find_iter_empty() {
+        assert_eq!(find_all("a*", "baab"), vec![(0, 0), (1, 3), (4, 4)]);
+        assert_eq!(find_all("", "aé"), vec![(0, 0), (1, 1), (3, 3)]);
+        assert_eq!(find_all("x*", ""), vec![(0, 0)]);
+        assert_eq!(find_all("a*", "b"), vec![(0, 0), (1, 1)]);
+        assert_eq!(find_all("a*", "bbb"), vec![(0, 0), (1, 1), (2, 2), (3, 3)]);
+    }
+
+    #[test]
+    fn find_overlapping_iter() {
+        let overlapping = |re: &str, s: &str| -> Vec<(usize, usize)> {
+            Regex::new(re).unwrap().find_overlapping_iter(s).collect()
+        };
+        assert_eq!(find_all("aa", "aaaa"), vec![(0, 2), (2, 4)]);
+        assert_eq!(overlapping("aa", "aaaa"), vec![(0, 2), (1, 3), (2, 4)]);
+        assert_eq!(overlapping("a+", "baa"), vec![(1, 3), (2, 3)]);
+        assert_eq!(overlapping("éé", "ééé"), vec![(0, 4), (2, 6)]);
+        assert_eq!(overlapping("a*", "ab"), vec![(0, 1), (1, 1), (2, 2)]);
+        assert_eq!(overlapping("^a", "aaa"), vec![(0, 1)]);
+        assert_eq!(overlapping("x", "aaa"), vec![]);
+        assert_eq!(overlapping("^a|b", "aab"), vec![(0, 1), (2, 3)]);
+        assert_eq!(overlapping(r"\ba", "aa a"), vec![(0, 1), (3, 4)]);
+        assert_eq!(overlapping(r"\Ba", "aaa"), vec![(1, 2), (2, 3)]);
+    }
+
+    #[test]
+    fn find_bytes() {
+        use regex::InvalidUtf8::*;
+
+        let re = Regex::new("b.a").unwrap();
+        let invalid = b"ab\xffab";
+        assert!(re.find_bytes(invalid, Error).is_err());
+        assert_eq!(re.find_bytes(invalid, Skip).unwrap(), None);
+        assert_eq!(re.find_bytes(invalid, Replace).unwrap(), Some((1, 4)));
+        assert_eq!(re.find_bytes("ébéaé".as_bytes(), Error).unwrap(), Some((2, 6)));
+
+        // A continuation byte without a leading byte.
+        let re = Regex::new("b+").unwrap();
+        assert_eq!(re.find_bytes(b"a\x80bb", Error).ok(), None);
+        assert_eq!(re.find_bytes(b"a\x80bb", Skip).unwrap(), Some((2, 4)));
+        assert_eq!(re.find_bytes(b"a\x80bb", Replace).unwrap(), Some((2, 4)));
+
+        let re = Regex::new("\u{FFFD}+").unwrap();
+        assert_eq!(re.find_bytes(b"a\xe2\x82\xffb", Replace).unwrap(), Some((1, 4)));
+        assert_eq!(re.find_bytes(b"a\xe2\x82\xffb", Skip).unwrap(), None);
+        assert_eq!(re.find_bytes(b"a\xe2\x82", Replace).unwrap(), Some((1, 3)));
+
+        // Invalid bytes look like non-word chars, not like the start or end of the input.
+        let re = Regex::new("^a").unwrap();
+        assert_eq!(re.find_bytes(b"\xffa", Skip).unwrap(), None);
+        assert_eq!(re.find_bytes(b"ba\xffa", Skip).unwrap(), None);
+        assert_eq!(re.find_bytes(b"\xffa", Replace).unwrap(), None);
+        let re = Regex::new(r"\ba").unwrap();
+        assert_eq!(re.find_bytes(b"\xffa", Skip).unwrap(), Some((1, 2)));
+        let re = Regex::new(r"a\b").unwrap();
+        assert_eq!(re.find_bytes(b"ab\xffa\xffb", Skip).unwrap(), Some((3, 4)));
+
+        // A match that covers an invalid byte is dropped, and the search resumes after it.
+        let re = Regex::new("a.*b").unwrap();
+        assert_eq!(re.find_bytes(b"xa\xffbab", Skip).unwrap(), Some((4, 6)));
+    }
+
+    #[test]
+    fn find_iter_anchored() {
+        assert_eq!(find_all("^a", "aaa"), vec![(0, 1)]);
+        assert_eq!(find_all("^a", "baa"), vec![]);
+    }
+
+    #[test]
+    fn find_iter_look_behind() {
+        assert_eq!(find_all("^a|b", "aab"), vec![(0, 1), (2, 3)]);
+        assert_eq!(find_all(r"\ba", "aa"), vec![(0, 1)]);
+        assert_eq!(find_all(r"\ba", "aa a"), vec![(0, 1), (3, 4)]);
+        assert_eq!(find_all(r"\Ba", "aaa"), vec![(1, 2), (2, 3)]);
+        assert_eq!(find_all(r"(?m)^a", "aa\na"), vec![(0, 1), (3, 4)]);
+        assert_eq!(find_all(r"a+", "aaa\naa"), vec![(0, 3), (4, 6)]);
+    }
 }
 
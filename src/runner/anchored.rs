@@ -35,6 +35,15 @@ impl<Ret: Copy + Debug + 'static> Engine<Ret> for AnchoredEngine<Ret> {
         }
     }
 
+    fn find_at(&self, s: &str, pos: usize) -> Option<(usize, usize, Ret)> {
+        // We can only match at the beginning of the input.
+        if pos == 0 {
+            self.find(s)
+        } else {
+            None
+        }
+    }
+
     fn clone_box(&self) -> Box<Engine<Ret>> {
         Box::new(self.clone())
     }
@@ -10,6 +10,7 @@ use std::fmt::Debug;
 //use dfa::{Dfa, PrefixPart, RetTrait};
 use dfa::PrefixPart;
 use itertools::Itertools;
+use look::Look;
 use memchr::memchr;
 use runner::Engine;
 use runner::program::TableInsts;
@@ -17,32 +18,40 @@ use runner::program::TableInsts;
 #[derive(Clone, Debug)]
 pub struct ForwardBackwardEngine<Ret> {
     forward: TableInsts<(usize, u8)>,
+    // The initial states of `forward`, indexed by `Look`. The one for `Look::Boundary` is used at
+    // the beginning of the input; the others are used for starting in the middle of the input (see
+    // `Nfa::anchor_resumable`).
+    forward_init: Vec<Option<usize>>,
     backward: TableInsts<Ret>,
     prefix: Prefix,
 }
 
-impl<Ret: Copy + Debug> ForwardBackwardEngine<Ret> {
-    pub fn new(forward: TableInsts<(usize, u8)>, prefix: Prefix, backward: TableInsts<Ret>) -> Self {
+impl<Ret: Copy + Debug + Into<usize>> ForwardBackwardEngine<Ret> {
+    pub fn new(forward: TableInsts<(usize, u8)>,
+               forward_init: Vec<Option<usize>>,
+               prefix: Prefix,
+               backward: TableInsts<Ret>) -> Self {
         ForwardBackwardEngine {
             forward: forward,
+            forward_init: forward_init,
             backward: backward,
             prefix: prefix,
         }
     }
 
-    fn find_with_searcher<SearchFn>(&self, input: &[u8], search: SearchFn)
+    fn find_with_searcher<SearchFn>(&self, input: &[u8], mut pos: usize, min_start: usize,
+                                    search: SearchFn)
     -> Option<(usize, usize, Ret)>
     where SearchFn: Fn(&[u8], usize) -> Option<usize> {
-        let mut pos = 0;
+        let init = match self.forward_init[Look::Boundary.as_usize()] {
+            Some(init) => init,
+            None => return None,
+        };
+
         while let Some(start) = search(input, pos) {
-            match self.forward.find_from(input, start, 0) {
+            match self.forward.find_from(input, start, init) {
                 Ok((end, (rev_state, look_ahead))) => {
-                    let rev_pos = end.saturating_sub(look_ahead as usize);
-                    let (start_pos, ret) = self.backward
-                        .longest_backward_find_from(input, rev_pos, rev_state)
-                        .expect("BUG: matched forward but failed to match backward");
-                    return Some((start_pos, rev_pos, ret));
-
+                    return Some(self.find_backward(input, end, rev_state, look_ahead, min_start));
                 },
                 Err(end) => {
                     pos = end + 1;
@@ -53,22 +62,21 @@ impl<Ret: Copy + Debug> ForwardBackwardEngine<Ret> {
         None
     }
 
-}
-
-impl<Ret: Copy + Debug + 'static> Engine<Ret> for ForwardBackwardEngine<Ret> {
-    fn find(&self, s: &str) -> Option<(usize, usize, Ret)> {
-        let input = s.as_bytes();
-        if self.forward.is_empty() {
-            return None;
-        }
-
+    // Looks for a match starting at `pos` or later, using the prefix to skip ahead. Matches that
+    // start before `min_start` are ignored.
+    fn search_from(&self, input: &[u8], pos: usize, min_start: usize)
+    -> Option<(usize, usize, Ret)> {
         match self.prefix {
             Prefix::Empty => self.find_with_searcher(
                 input,
+                pos,
+                min_start,
                 |s, pos| if pos <= s.len() { Some(pos) } else { None }
             ),
             Prefix::ByteSet { ref bytes, offset } => self.find_with_searcher(
                 input,
+                pos,
+                min_start,
                 |s, pos| if pos + offset <= s.len() {
                         s[(pos + offset)..].iter().position(|c| bytes[*c as usize]).map(|x| x + pos)
                     } else {
@@ -77,6 +85,8 @@ impl<Ret: Copy + Debug + 'static> Engine<Ret> for ForwardBackwardEngine<Ret> {
             ),
             Prefix::Byte { byte, offset } => self.find_with_searcher(
                 input,
+                pos,
+                min_start,
                 |s, pos| if pos + offset <= s.len() {
                     memchr(byte, &input[(pos + offset)..]).map(|x| x + pos)
                 } else {
@@ -87,6 +97,73 @@ impl<Ret: Copy + Debug + 'static> Engine<Ret> for ForwardBackwardEngine<Ret> {
         }
     }
 
+    // Having found the end of a match, runs backwards to find the start of it. The start will be
+    // at or after `min_start`, but we still look at the char before `min_start`, in case the match
+    // needs to look behind at it.
+    fn find_backward(&self, input: &[u8], end: usize, rev_state: usize, look_ahead: u8,
+                     min_start: usize)
+    -> (usize, usize, Ret) {
+        let rev_pos = end.saturating_sub(look_ahead as usize);
+        let found = if min_start == 0 {
+            self.backward.longest_backward_find_from(input, rev_pos, rev_state)
+        } else {
+            let (mut found, state) =
+                self.backward.longest_backward_find_between(input, rev_pos, min_start, rev_state);
+            if let Some(state) = state {
+                let mut prev = min_start - 1;
+                while input[prev] & 0xC0 == 0x80 {
+                    prev -= 1;
+                }
+
+                // A match that starts before `min_start` is only allowed if the part before
+                // `min_start` was just the look-behind.
+                let (before, _) =
+                    self.backward.longest_backward_find_between(input, min_start, prev, state);
+                if let Some((start, look_behind)) = before {
+                    if start + look_behind.into() >= min_start {
+                        found = before;
+                    }
+                }
+            }
+            found
+        };
+
+        let (start_pos, ret) = found.expect("BUG: matched forward but failed to match backward");
+        (start_pos, rev_pos, ret)
+    }
+}
+
+impl<Ret: Copy + Debug + Into<usize> + 'static> Engine<Ret> for ForwardBackwardEngine<Ret> {
+    fn find(&self, s: &str) -> Option<(usize, usize, Ret)> {
+        if self.forward.is_empty() {
+            return None;
+        }
+
+        self.search_from(s.as_bytes(), 0, 0)
+    }
+
+    fn find_at(&self, s: &str, pos: usize) -> Option<(usize, usize, Ret)> {
+        if pos == 0 {
+            return self.find(s);
+        }
+        if self.forward.is_empty() || pos > s.len() {
+            return None;
+        }
+
+        // Start from the initial state that knows about the previous char. If that fails, the
+        // prefix can take us to the next place worth trying.
+        let input = s.as_bytes();
+        let init = match self.forward_init[Look::before(s, pos).as_usize()] {
+            Some(init) => init,
+            None => return None,
+        };
+        match self.forward.find_from(input, pos, init) {
+            Ok((end, (rev_state, look_ahead))) =>
+                Some(self.find_backward(input, end, rev_state, look_ahead, pos)),
+            Err(end) => self.search_from(input, end + 1, pos),
+        }
+    }
+
     fn clone_box(&self) -> Box<Engine<Ret>> {
         Box::new(self.clone())
     }
@@ -2,6 +2,10 @@ use std::fmt::Debug;
 
 pub trait Engine<Ret: Debug>: Debug {
     fn find(&self, s: &str) -> Option<(usize, usize, Ret)>;
+    // Like `find`, but only looks for matches starting at or after `pos`, which must be a char
+    // boundary. Unlike running `find` on `&s[pos..]`, this sees the char before `pos`, so
+    // look-behinds at `pos` work properly.
+    fn find_at(&self, s: &str, pos: usize) -> Option<(usize, usize, Ret)>;
     fn clone_box(&self) -> Box<Engine<Ret>>;
 }
 
@@ -152,6 +152,32 @@ impl<Ret: Copy + Debug> TableInsts<Ret> {
         }
     }
 
+    /// Like `longest_backward_find_from`, but stops at `min_pos` instead of at the beginning of
+    /// the input (and so it never looks at `accept_at_eoi`).
+    ///
+    /// Returns the longest match, together with the state that we were in at `min_pos` (or `None`
+    /// if we ran out of transitions before getting there).
+    pub fn longest_backward_find_between(&self, input: &[u8], pos: usize, min_pos: usize,
+                                         mut state: usize)
+    -> (Option<(usize, Ret)>, Option<usize>) {
+        let mut ret = None;
+        for pos in (min_pos..pos).rev() {
+            if let Some(next_ret) = self.accept[state] {
+                ret = Some((pos + 1, next_ret));
+            }
+            if let Some(next_state) = self.next_state(state, input[pos]) {
+                state = next_state;
+            } else {
+                return (ret, None);
+            }
+        }
+
+        if let Some(next_ret) = self.accept[state] {
+            ret = Some((min_pos, next_ret));
+        }
+        (ret, Some(state))
+    }
+
     pub fn is_empty(&self) -> bool {
         self.num_states() == 0
     }
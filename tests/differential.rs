@@ -0,0 +1,183 @@
+// Copyright 2015-2016 Joe Neeman.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Compares random regexes on random inputs against the `regex` crate.
+//
+// Everything here is deterministic: the regexes come from a fixed seed (which can be changed by
+// setting the `REGEX_DFA_TEST_SEED` environment variable), and the inputs for each regex come
+// from another fixed seed. Failures are shrunk, and the error message has everything needed to
+// reproduce them.
+
+extern crate quickcheck;
+extern crate rand;
+extern crate regex;
+extern crate regex_dfa;
+
+use quickcheck::{Arbitrary, Gen, QuickCheck, StdGen, TestResult};
+use rand::{Rng, SeedableRng, StdRng, XorShiftRng};
+use std::env;
+use std::fmt;
+
+// How many inputs to try for each regex.
+const NUM_INPUTS: usize = 10000;
+// The longest input to try.
+const MAX_INPUT_LEN: usize = 16;
+// The alphabet for inputs, which is enough to exercise word boundaries and line anchors.
+const ALPHABET: [char; 4] = ['a', 'b', ' ', '\n'];
+
+// A small regex over the same alphabet as the inputs. We keep the syntax tree around (instead of
+// just the string) so that we can shrink it.
+#[derive(Clone)]
+enum Re {
+    Atom(&'static str),
+    Concat(Box<Re>, Box<Re>),
+    Alt(Box<Re>, Box<Re>),
+    Star(Box<Re>),
+    Plus(Box<Re>),
+    Opt(Box<Re>),
+}
+
+impl fmt::Display for Re {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Re::Atom(s) => write!(f, "{}", s),
+            Re::Concat(ref a, ref b) => write!(f, "{}{}", a, b),
+            Re::Alt(ref a, ref b) => write!(f, "(?:{}|{})", a, b),
+            Re::Star(ref a) => write!(f, "(?:{})*", a),
+            Re::Plus(ref a) => write!(f, "(?:{})+", a),
+            Re::Opt(ref a) => write!(f, "(?:{})?", a),
+        }
+    }
+}
+
+// Failing tests print their arguments with `Debug`, and the regex is more useful than the tree.
+impl fmt::Debug for Re {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.to_string())
+    }
+}
+
+fn gen_re<G: Gen>(g: &mut G, depth: u32) -> Re {
+    if depth == 0 || g.gen_weighted_bool(3) {
+        let atoms = ["a", "b", " ", ".", "[ab]", "[^a]",
+                     "^", "$", r"\b", r"\B", "(?m:^)", "(?m:$)"];
+        return Re::Atom(*g.choose(&atoms).unwrap());
+    }
+
+    let choice = g.gen_range(0, 6);
+    let mut sub = || Box::new(gen_re(g, depth - 1));
+    match choice {
+        0 | 1 => Re::Concat(sub(), sub()),
+        2 => Re::Alt(sub(), sub()),
+        3 => Re::Star(sub()),
+        4 => Re::Plus(sub()),
+        _ => Re::Opt(sub()),
+    }
+}
+
+impl Arbitrary for Re {
+    fn arbitrary<G: Gen>(g: &mut G) -> Re {
+        gen_re(g, 4)
+    }
+
+    // Tries the subexpressions on their own first, and then shrinks them in place.
+    fn shrink(&self) -> Box<Iterator<Item=Re>> {
+        let mut ret = Vec::new();
+        match *self {
+            Re::Atom(s) => {
+                if s != "a" {
+                    ret.push(Re::Atom("a"));
+                }
+            },
+            Re::Concat(ref a, ref b) | Re::Alt(ref a, ref b) => {
+                let rebuild = |x: Re, y: Re| match *self {
+                    Re::Concat(..) => Re::Concat(Box::new(x), Box::new(y)),
+                    _ => Re::Alt(Box::new(x), Box::new(y)),
+                };
+                ret.push((**a).clone());
+                ret.push((**b).clone());
+                ret.extend(a.shrink().map(|x| rebuild(x, (**b).clone())));
+                ret.extend(b.shrink().map(|y| rebuild((**a).clone(), y)));
+            },
+            Re::Star(ref a) | Re::Plus(ref a) | Re::Opt(ref a) => {
+                let rebuild = |x: Re| match *self {
+                    Re::Star(..) => Re::Star(Box::new(x)),
+                    Re::Plus(..) => Re::Plus(Box::new(x)),
+                    _ => Re::Opt(Box::new(x)),
+                };
+                ret.push((**a).clone());
+                ret.extend(a.shrink().map(rebuild));
+            },
+        }
+        Box::new(ret.into_iter())
+    }
+}
+
+fn disagrees(ours: &regex_dfa::Regex, theirs: &regex::Regex, input: &str) -> bool {
+    ours.is_match(input) != theirs.is_match(input)
+}
+
+// Deletes chars from `input` for as long as the two regexes still disagree on it.
+fn shrink_input(ours: &regex_dfa::Regex, theirs: &regex::Regex, mut input: String) -> String {
+    let mut i = 0;
+    while i < input.len() {
+        let mut smaller = input.clone();
+        smaller.remove(i);
+        if disagrees(ours, theirs, &smaller) {
+            input = smaller;
+        } else {
+            i += 1;
+        }
+    }
+    input
+}
+
+fn agrees(re: Re) -> TestResult {
+    let re = re.to_string();
+    let ours = regex_dfa::Regex::new(&re);
+    let theirs = regex::Regex::new(&re);
+    match (ours, theirs) {
+        (Ok(ours), Ok(theirs)) => {
+            let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+            for _ in 0..NUM_INPUTS {
+                let len = rng.gen_range(0, MAX_INPUT_LEN + 1);
+                let input: String = (0..len).map(|_| *rng.choose(&ALPHABET).unwrap()).collect();
+                if disagrees(&ours, &theirs, &input) {
+                    let input = shrink_input(&ours, &theirs, input);
+                    return TestResult::error(&format!(
+                        "regex {:?} disagrees on input {:?}: regex_dfa says {}, regex says {}",
+                        re, input, ours.is_match(&input), theirs.is_match(&input)));
+                }
+            }
+            TestResult::passed()
+        },
+        (Err(_), Err(_)) => TestResult::discard(),
+        (Ok(_), Err(e)) => {
+            TestResult::error(&format!("regex {:?} was rejected by the regex crate: {}", re, e))
+        },
+        (Err(e), Ok(_)) => {
+            TestResult::error(&format!("regex {:?} was rejected by regex_dfa: {}", re, e))
+        },
+    }
+}
+
+// The seed for generating regexes.
+fn seed() -> usize {
+    env::var("REGEX_DFA_TEST_SEED").ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0x5eed)
+}
+
+#[test]
+fn differential_is_match() {
+    let rng: StdRng = SeedableRng::from_seed(&[seed()][..]);
+    QuickCheck::new()
+        .gen(StdGen::new(rng, 20))
+        .tests(200)
+        .quickcheck(agrees as fn(Re) -> TestResult);
+}
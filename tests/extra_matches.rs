@@ -0,0 +1,82 @@
+// Copyright 2015-2016 Joe Neeman.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Hand-written tests in the same format as matches.rs. Unlike matches.rs, this file isn't
+// generated, so new tests can go here.
+
+extern crate regex_dfa;
+
+use regex_dfa::Regex;
+
+macro_rules! mat(
+    ($name:ident, $re:expr, $text:expr, $loc:expr) => (
+        #[test]
+        fn $name() {
+            let text = $text;
+            let expected: Option<(usize, usize)> = $loc;
+            let r = Regex::new($re).unwrap();
+            let pos = r.find(text);
+            if expected != pos {
+                panic!("For RE '{}' against '{:?}', expected '{:?}' but got '{:?}'",
+                       $re, text, expected, pos);
+            }
+        }
+    );
+);
+
+macro_rules! no_mat(
+    ($name:ident, $re:expr, $text:expr) => (
+        mat!($name, $re, $text, None);
+    );
+);
+
+// Unicode chars that take more than one byte.
+mat!(uni_euro, "\u{20ac}", "x\u{20ac}y", Some((1, 4)));
+mat!(uni_euro_dot, "x.y", "x\u{20ac}y", Some((0, 5)));
+mat!(uni_euro_range, "[\u{20a0}-\u{20cf}]+", "a\u{20ac}\u{20ad}b", Some((1, 7)));
+no_mat!(uni_euro_prefix, "\u{20ac}", "\u{20ad}\u{20ab}");
+no_mat!(uni_euro_neg, "x[^\u{20ac}]y", "x\u{20ac}y");
+
+// Word boundaries.
+mat!(boundary_foo, r"\bfoo\b", "a foo b", Some((2, 5)));
+mat!(boundary_foo_start, r"\bfoo\b", "foo b", Some((0, 3)));
+mat!(boundary_foo_end, r"\bfoo\b", "a foo", Some((2, 5)));
+mat!(boundary_foo_later, r"\bfoo\b", "foobar foo", Some((7, 10)));
+no_mat!(boundary_foo_prefix, r"\bfoo\b", "foobar");
+no_mat!(boundary_foo_suffix, r"\bfoo\b", "barfoo");
+mat!(boundary_not, r"\Boo\B", "foo book", Some((5, 7)));
+no_mat!(boundary_not_edges, r"\Bfoo", "foo");
+mat!(boundary_empty_input, r"\B", "", Some((0, 0)));
+no_mat!(boundary_empty_input_not, r"\b", "");
+
+// Multi-line mode.
+mat!(match_flag_multi_line, "(?m)^foo$", "bar\nfoo\nbaz", Some((4, 7)));
+mat!(match_flag_multi_first, "(?m)^foo$", "foo\nbar", Some((0, 3)));
+mat!(match_flag_multi_last, "(?m)^foo$", "bar\nfoo", Some((4, 7)));
+mat!(match_flag_multi_empty_line, "(?m)^$", "a\n\nb", Some((2, 2)));
+no_mat!(match_flag_multi_mid, "(?m)^foo$", "bar\nxfoo\nbaz");
+no_mat!(match_flag_multi_trailing, "(?m)^foo$", "bar\nfoox\nbaz");
+no_mat!(match_flag_multi_off, "^foo$", "bar\nfoo\nbaz");
+
+// Anchors for the beginning and end of the text, which ignore multi-line mode.
+mat!(match_text_anchors, r"\Afoo\z", "foo", Some((0, 3)));
+no_mat!(match_text_anchors_prefix, r"\Afoo\z", "xfoo");
+no_mat!(match_text_start_prefix, r"\Afoo", "xfoo");
+no_mat!(match_text_anchors_suffix, r"\Afoo\z", "foox");
+no_mat!(match_text_anchors_lines, r"\Afoo\z", "bar\nfoo\nbaz");
+no_mat!(match_text_anchors_trailing_nl, r"\Afoo\z", "foo\n");
+no_mat!(match_text_anchors_multi, r"(?m)\Afoo\z", "bar\nfoo\nbaz");
+mat!(match_text_anchors_multi_first, r"(?m)\Afoo$", "foo\nbar", Some((0, 3)));
+no_mat!(match_text_anchors_multi_last, r"(?m)^foo\z", "foo\nbar");
+
+// Lazy repetitions.
+mat!(match_lazy_star, "a*?", "aa", Some((0, 0)));
+mat!(match_lazy_plus, "a+?", "aaa", Some((0, 1)));
+mat!(match_lazy_question, "ab??", "ab", Some((0, 1)));
+mat!(match_lazy_then_literal, "a*?b", "aab", Some((0, 3)));
+mat!(match_lazy_range, "a{2,4}?", "aaaa", Some((0, 2)));
@@ -0,0 +1,65 @@
+// Copyright 2015-2016 Joe Neeman.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Feeds random (and mostly invalid) regexes to the compiler, checking that it never panics.
+//
+// The regexes come from a fixed seed, which can be changed by setting the `REGEX_DFA_TEST_SEED`
+// environment variable.
+
+extern crate quickcheck;
+extern crate rand;
+extern crate regex_dfa;
+
+use quickcheck::{Arbitrary, Gen, QuickCheck, StdGen};
+use rand::{Rng, SeedableRng, StdRng};
+use regex_dfa::Regex;
+use std::env;
+
+// A string made by gluing together random pieces of regex syntax. Most of these won't parse.
+#[derive(Clone, Debug)]
+struct Pattern(String);
+
+impl Arbitrary for Pattern {
+    fn arbitrary<G: Gen>(g: &mut G) -> Pattern {
+        let pieces = ["a", "b", "é", "\n", ".", "^", "$", r"\b", r"\B", r"\A", r"\z",
+                      "(", ")", "(?:", "(?i)", "(?m)", "(?s)", "(?U)", "(?-i)", "|",
+                      "*", "+", "?", "*?", "+?", "{2}", "{1,3}", "{0,}", "{", "}",
+                      "[", "]", "[^", "-", "a-z", "[[:alpha:]]", r"\d", r"\w", r"\s", r"\W",
+                      r"\pN", r"\p{Greek}", r"\x{10FFFF}", r"\"];
+        let len = g.gen_range(0, g.size() + 1);
+        Pattern((0..len).map(|_| *g.choose(&pieces).unwrap()).collect())
+    }
+}
+
+fn no_panic(pat: Pattern) -> bool {
+    let inputs = ["", "a", "ab ba", "é\nb", "AB1 é"];
+    if let Ok(re) = Regex::new(&pat.0) {
+        for input in &inputs {
+            re.find(input);
+            re.find_iter(input).count();
+        }
+    }
+    let _ = Regex::new_bounded(&pat.0, 10);
+    true
+}
+
+// The seed for generating regexes.
+fn seed() -> usize {
+    env::var("REGEX_DFA_TEST_SEED").ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0x5eed)
+}
+
+#[test]
+fn regex_new_no_panic() {
+    let rng: StdRng = SeedableRng::from_seed(&[seed()][..]);
+    QuickCheck::new()
+        .gen(StdGen::new(rng, 10))
+        .tests(2000)
+        .quickcheck(no_panic as fn(Pattern) -> bool);
+}